@@ -0,0 +1,159 @@
+// backend/src/cache.rs
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use std::time::UNIX_EPOCH;
+
+/// How a static file request was served, attached to the response as an
+/// extension for the access log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStatus {
+    /// The client's cached copy was still valid and no body was sent.
+    pub hit: bool,
+    /// A precompressed `.br`/`.gz` sibling was sent instead of the file.
+    pub precompressed: bool,
+}
+
+/// Cache lifetimes for static files, in seconds.
+#[derive(Debug, Clone)]
+pub struct CachePolicy {
+    /// HTML pages, kept short so content updates show up quickly.
+    pub html_max_age: u64,
+    /// Files whose name carries a content hash, e.g. `app.3f9dc80a.js`.
+    /// These never change under the same name and are marked immutable.
+    pub hashed_max_age: u64,
+    /// Assets that rarely change but are not hashed, such as images and
+    /// fonts. ETags let clients revalidate them once stale. The unhashed WASM
+    /// bundle is not among them: it must expire together with its JavaScript
+    /// glue, or a deploy can pair new glue with an old cached module.
+    pub long_lived_max_age: u64,
+    /// Extensions that get `long_lived_max_age`.
+    pub long_lived_extensions: Vec<String>,
+    /// Everything else, e.g. CSS, JavaScript and WASM.
+    pub default_max_age: u64,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        CachePolicy {
+            html_max_age: 300,
+            hashed_max_age: 365 * 24 * 60 * 60,
+            long_lived_max_age: 30 * 24 * 60 * 60,
+            long_lived_extensions: [
+                "png", "jpg", "jpeg", "gif", "webp", "avif", "svg", "ico", "woff", "woff2", "pdf",
+            ]
+            .map(String::from)
+            .to_vec(),
+            default_max_age: 24 * 60 * 60,
+        }
+    }
+}
+
+impl CachePolicy {
+    /// The `Cache-Control` value for a request path.
+    pub fn cache_control(&self, path: &str) -> String {
+        let file_name = path.rsplit('/').next().unwrap_or("");
+        let extension = file_name
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_ascii_lowercase())
+            .unwrap_or_default();
+
+        if file_name.is_empty() || extension == "html" || extension == "htm" {
+            format!("public, max-age={}, must-revalidate", self.html_max_age)
+        } else if is_hashed(file_name) {
+            format!("public, max-age={}, immutable", self.hashed_max_age)
+        } else if self.long_lived_extensions.contains(&extension) {
+            format!("public, max-age={}", self.long_lived_max_age)
+        } else {
+            format!("public, max-age={}", self.default_max_age)
+        }
+    }
+}
+
+/// Whether a file name has a content-hash segment of at least eight hex
+/// digits, as in `bundle.3f9dc80a.wasm` or `bundle-3f9dc80a.wasm`.
+fn is_hashed(file_name: &str) -> bool {
+    let stem = file_name.rsplit_once('.').map_or(file_name, |(stem, _)| stem);
+    stem.split(['.', '-', '_'])
+        .skip(1)
+        .any(|part| part.len() >= 8 && part.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+/// Weak validator built from the file's size, modification time and encoding,
+/// all of which `ServeDir` reports in its response headers.
+fn etag(headers: &HeaderMap) -> Option<HeaderValue> {
+    let value = |name| headers.get(name).and_then(|value: &HeaderValue| value.to_str().ok());
+    let length = value(header::CONTENT_LENGTH)?;
+    let modified = httpdate::parse_http_date(value(header::LAST_MODIFIED)?)
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+    let tag = match value(header::CONTENT_ENCODING) {
+        Some(encoding) => format!("W/\"{:x}-{:x}-{}\"", modified, length.parse::<u64>().ok()?, encoding),
+        None => format!("W/\"{:x}-{:x}\"", modified, length.parse::<u64>().ok()?),
+    };
+    HeaderValue::from_str(&tag).ok()
+}
+
+/// Whether an `If-None-Match` header matches `etag` under weak comparison.
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    if_none_match.trim() == "*" || if_none_match.split(',').any(|tag| opaque(tag) == opaque(etag))
+}
+
+/// Adds `Cache-Control` and `ETag` to static file responses and answers
+/// matching `If-None-Match` revalidations with `304 Not Modified`.
+pub async fn static_headers(State(policy): State<CachePolicy>, request: Request, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    let cacheable = matches!(*request.method(), Method::GET | Method::HEAD);
+    let if_none_match = request
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from);
+
+    let mut response = next.run(request).await;
+    let status = response.status();
+    if !cacheable || !(status == StatusCode::OK || status == StatusCode::NOT_MODIFIED) {
+        return response;
+    }
+
+    if let Ok(cache_control) = HeaderValue::from_str(&policy.cache_control(&path)) {
+        response.headers_mut().insert(header::CACHE_CONTROL, cache_control);
+    }
+    let precompressed = response.headers().contains_key(header::CONTENT_ENCODING);
+    response.extensions_mut().insert(CacheStatus {
+        hit: status == StatusCode::NOT_MODIFIED,
+        precompressed,
+    });
+    let Some(etag) = etag(response.headers()) else {
+        return response;
+    };
+
+    if let Some(if_none_match) = if_none_match
+        && etag_matches(&if_none_match, etag.to_str().unwrap_or(""))
+    {
+        let mut not_modified = Response::new(Body::empty());
+        *not_modified.status_mut() = StatusCode::NOT_MODIFIED;
+        let headers = not_modified.headers_mut();
+        for name in [header::CACHE_CONTROL, header::LAST_MODIFIED, header::VARY] {
+            if let Some(value) = response.headers().get(&name) {
+                headers.insert(name, value.clone());
+            }
+        }
+        headers.insert(header::ETAG, etag);
+        not_modified.extensions_mut().insert(CacheStatus {
+            hit: true,
+            precompressed: false,
+        });
+        return not_modified;
+    }
+
+    response.headers_mut().insert(header::ETAG, etag);
+    response
+}
@@ -0,0 +1,215 @@
+// backend/src/client_ip.rs
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::HeaderMap,
+    middleware::Next,
+    response::Response,
+};
+use log::warn;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+
+/// The resolved address of the client that made a request, stored as a
+/// request extension by [`resolve_client_ip`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+impl ClientIp {
+    /// The resolved client address, falling back to the peer address when
+    /// the request was not passed through [`resolve_client_ip`].
+    pub fn from_request(request: &Request) -> Option<IpAddr> {
+        let extensions = request.extensions();
+        extensions
+            .get::<ClientIp>()
+            .map(|ClientIp(ip)| *ip)
+            .or_else(|| extensions.get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(addr)| addr.ip()))
+    }
+}
+
+/// Headers proxies use to pass on the client address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForwardingHeader {
+    /// RFC 7239 `Forwarded: for=...`, one element per hop.
+    Forwarded,
+    /// `X-Forwarded-For`, a comma-separated list with one entry per hop.
+    XForwardedFor,
+    /// `X-Real-IP`, a single address set by the proxy.
+    XRealIp,
+    /// `CF-Connecting-IP`, a single address set by Cloudflare.
+    CfConnectingIp,
+}
+
+/// An address range in CIDR notation; a bare address is a single-host range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpRange {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl IpRange {
+    pub fn parse(range: &str) -> Option<Self> {
+        let (addr, prefix) = match range.trim().split_once('/') {
+            Some((addr, prefix)) => (addr.parse::<IpAddr>().ok()?, Some(prefix.parse::<u8>().ok()?)),
+            None => (range.trim().parse::<IpAddr>().ok()?, None),
+        };
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(max);
+        (prefix <= max).then_some(IpRange { network: addr, prefix })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Which peers may report the client address, and the headers they report
+/// it in.
+#[derive(Debug, Clone)]
+pub struct TrustedProxies {
+    proxies: Vec<IpRange>,
+    /// Consulted in order; the first header yielding an address wins.
+    pub headers: Vec<ForwardingHeader>,
+}
+
+impl Default for TrustedProxies {
+    /// Trusts proxies on the same host, such as the reverse proxy or tunnel
+    /// the site is deployed behind.
+    fn default() -> Self {
+        TrustedProxies {
+            proxies: vec![
+                IpRange {
+                    network: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 0)),
+                    prefix: 8,
+                },
+                IpRange {
+                    network: IpAddr::V6(Ipv6Addr::LOCALHOST),
+                    prefix: 128,
+                },
+            ],
+            headers: vec![
+                ForwardingHeader::Forwarded,
+                ForwardingHeader::XForwardedFor,
+                ForwardingHeader::XRealIp,
+                ForwardingHeader::CfConnectingIp,
+            ],
+        }
+    }
+}
+
+impl TrustedProxies {
+    /// Trusts no proxies: the peer address is always the client.
+    pub fn none() -> Self {
+        TrustedProxies {
+            proxies: Vec::new(),
+            ..TrustedProxies::default()
+        }
+    }
+
+    /// The defaults plus the comma-separated addresses and CIDR ranges in
+    /// `TRUSTED_PROXIES`.
+    pub fn from_env() -> Self {
+        let mut trusted = TrustedProxies::default();
+        if let Ok(proxies) = std::env::var("TRUSTED_PROXIES") {
+            for entry in proxies.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+                match IpRange::parse(entry) {
+                    Some(range) => trusted.proxies.push(range),
+                    None => warn!("Ignoring invalid trusted proxy: {}", entry),
+                }
+            }
+        }
+        trusted
+    }
+
+    /// Trusts the peers in `range`.
+    pub fn trust(mut self, range: IpRange) -> Self {
+        self.proxies.push(range);
+        self
+    }
+
+    pub fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.proxies.iter().any(|range| range.contains(ip))
+    }
+
+    /// The client address for a request from `peer`. Headers are only
+    /// believed when the peer is a trusted proxy.
+    pub fn resolve(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.is_trusted(peer) {
+            return peer;
+        }
+        self.headers
+            .iter()
+            .find_map(|header| match header {
+                ForwardingHeader::Forwarded => self.walk(header_values(headers, "forwarded").flat_map(forwarded_for)),
+                ForwardingHeader::XForwardedFor => {
+                    self.walk(header_values(headers, "x-forwarded-for").flat_map(|v| v.split(',')).filter_map(parse_ip))
+                }
+                ForwardingHeader::XRealIp => header_values(headers, "x-real-ip").next().and_then(parse_ip),
+                ForwardingHeader::CfConnectingIp => header_values(headers, "cf-connecting-ip").next().and_then(parse_ip),
+            })
+            .unwrap_or(peer)
+    }
+
+    /// Each proxy appends the address it received the request from, so the
+    /// rightmost untrusted hop is the first one we can't vouch for. If every
+    /// hop is trusted, the leftmost is the client.
+    fn walk(&self, hops: impl Iterator<Item = IpAddr>) -> Option<IpAddr> {
+        let hops: Vec<IpAddr> = hops.collect();
+        hops.iter()
+            .rev()
+            .find(|&&ip| !self.is_trusted(ip))
+            .or(hops.first())
+            .copied()
+    }
+}
+
+fn header_values<'a>(headers: &'a HeaderMap, name: &str) -> impl Iterator<Item = &'a str> {
+    headers.get_all(name).iter().filter_map(|value| value.to_str().ok())
+}
+
+/// Parses an address as proxies write it: bare, quoted, with a port, or as a
+/// bracketed IPv6 address with an optional port.
+fn parse_ip(value: &str) -> Option<IpAddr> {
+    let value = value.trim().trim_matches('"');
+    if let Some(rest) = value.strip_prefix('[') {
+        return rest.split_once(']')?.0.parse().ok();
+    }
+    value
+        .parse()
+        .ok()
+        .or_else(|| value.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+}
+
+/// The `for=` addresses of a `Forwarded` header value, in hop order.
+/// Obfuscated and `unknown` identifiers are skipped.
+fn forwarded_for(value: &str) -> impl Iterator<Item = IpAddr> + '_ {
+    value.split(',').filter_map(|element| {
+        element.split(';').find_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            key.trim().eq_ignore_ascii_case("for").then(|| parse_ip(value)).flatten()
+        })
+    })
+}
+
+/// Resolves the client address from the peer address and trusted forwarding
+/// headers and stores it as a [`ClientIp`] request extension.
+pub async fn resolve_client_ip(
+    State(trusted): State<Arc<TrustedProxies>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if let Some(ConnectInfo(addr)) = request.extensions().get::<ConnectInfo<SocketAddr>>().copied() {
+        let client = trusted.resolve(addr.ip(), request.headers());
+        request.extensions_mut().insert(ClientIp(client));
+    }
+    next.run(request).await
+}
@@ -0,0 +1,222 @@
+// backend/src/encoding.rs
+use crate::routes::GameState;
+use axum::{
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Serialize;
+
+pub const RLE_CONTENT_TYPE: &str = "application/x-life-rle";
+pub const PACKED_CONTENT_TYPE: &str = "application/vnd.game-of-life.packed+json";
+
+/// Wire encoding for a board, chosen with `?format=` or the `Accept` header.
+///
+/// - `json`: the grid as an array with one number per cell.
+/// - `packed`: JSON with the grid bit-packed and base64 encoded.
+/// - `binary`: the bit-packed grid as raw `application/octet-stream`, with
+///   dimensions and offset in the `X-Grid-*` headers.
+/// - `rle`: the standard Life RLE pattern format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StateFormat {
+    #[default]
+    Json,
+    Packed,
+    Binary,
+    Rle,
+}
+
+impl StateFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Some(StateFormat::Json),
+            "packed" | "base64" => Some(StateFormat::Packed),
+            "binary" | "bin" | "octet-stream" => Some(StateFormat::Binary),
+            "rle" => Some(StateFormat::Rle),
+            _ => None,
+        }
+    }
+
+    fn from_media_type(media_type: &str) -> Option<Self> {
+        match media_type {
+            "application/json" => Some(StateFormat::Json),
+            PACKED_CONTENT_TYPE => Some(StateFormat::Packed),
+            "application/octet-stream" => Some(StateFormat::Binary),
+            RLE_CONTENT_TYPE => Some(StateFormat::Rle),
+            _ => None,
+        }
+    }
+
+    /// Picks the highest-weighted supported media type from an `Accept` header.
+    pub fn from_accept(accept: &str) -> Option<Self> {
+        let mut candidates: Vec<(f32, usize, StateFormat)> = accept
+            .split(',')
+            .enumerate()
+            .filter_map(|(position, entry)| {
+                let mut params = entry.split(';');
+                let media_type = params.next()?.trim().to_ascii_lowercase();
+                let quality = params
+                    .filter_map(|p| p.trim().strip_prefix("q="))
+                    .find_map(|q| q.parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                let format = StateFormat::from_media_type(&media_type)?;
+                (quality > 0.0).then_some((quality, position, format))
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+        candidates.first().map(|&(_, _, format)| format)
+    }
+
+    /// Encodes a board in this format.
+    pub fn respond(self, state: GameState) -> Response {
+        match self {
+            StateFormat::Json => Json(state).into_response(),
+            StateFormat::Packed => Json(PackedState {
+                encoding: "bitpacked-base64",
+                data: STANDARD.encode(pack_bits(&state.grid)),
+                width: state.width,
+                height: state.height,
+                generation: state.generation,
+                x: state.x,
+                y: state.y,
+            })
+            .into_response(),
+            StateFormat::Binary => {
+                let mut headers = dimension_headers(&state);
+                headers.insert(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static("application/octet-stream"),
+                );
+                (StatusCode::OK, headers, pack_bits(&state.grid)).into_response()
+            }
+            StateFormat::Rle => {
+                let mut headers = dimension_headers(&state);
+                headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(RLE_CONTENT_TYPE));
+                let body = encode_rle(&state.grid, state.width, state.height);
+                (StatusCode::OK, headers, body).into_response()
+            }
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for StateFormat {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let requested = parts.uri.query().and_then(|query| {
+            query
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(key, _)| *key == "format")
+                .map(|(_, value)| value)
+        });
+        if let Some(name) = requested {
+            return StateFormat::from_name(name)
+                .ok_or_else(|| (StatusCode::BAD_REQUEST, format!("Unknown format: {}", name)));
+        }
+
+        Ok(parts
+            .headers
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .and_then(StateFormat::from_accept)
+            .unwrap_or_default())
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct PackedState {
+    encoding: &'static str,
+    data: String,
+    width: usize,
+    height: usize,
+    generation: usize,
+    x: usize,
+    y: usize,
+}
+
+fn dimension_headers(state: &GameState) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert("x-grid-width", HeaderValue::from(state.width));
+    headers.insert("x-grid-height", HeaderValue::from(state.height));
+    headers.insert("x-grid-x", HeaderValue::from(state.x));
+    headers.insert("x-grid-y", HeaderValue::from(state.y));
+    headers.insert("x-generation", HeaderValue::from(state.generation));
+    headers
+}
+
+/// Packs cells row-major into bytes, eight cells per byte, most significant
+/// bit first. The final byte is zero-padded.
+pub fn pack_bits(grid: &[u8]) -> Vec<u8> {
+    grid.chunks(8)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0u8, |byte, (bit, &cell)| byte | (((cell != 0) as u8) << (7 - bit)))
+        })
+        .collect()
+}
+
+/// Inverse of [`pack_bits`] for a board of `len` cells.
+pub fn unpack_bits(packed: &[u8], len: usize) -> Vec<u8> {
+    (0..len)
+        .map(|i| packed.get(i / 8).map_or(0, |byte| (byte >> (7 - i % 8)) & 1))
+        .collect()
+}
+
+/// Encodes a board in Life RLE, omitting trailing dead cells and rows.
+pub fn encode_rle(grid: &[u8], width: usize, height: usize) -> String {
+    const LINE_LIMIT: usize = 70;
+
+    fn push_run(tokens: &mut Vec<String>, count: usize, tag: char) {
+        if count == 1 {
+            tokens.push(tag.to_string());
+        } else if count > 1 {
+            tokens.push(format!("{}{}", count, tag));
+        }
+    }
+
+    let mut tokens = Vec::new();
+    let mut pending_rows = 0;
+    for row in grid.chunks(width).take(height) {
+        let Some(last_alive) = row.iter().rposition(|&cell| cell != 0) else {
+            pending_rows += 1;
+            continue;
+        };
+        if !tokens.is_empty() {
+            push_run(&mut tokens, pending_rows + 1, '$');
+        } else if pending_rows > 0 {
+            push_run(&mut tokens, pending_rows, '$');
+        }
+        pending_rows = 0;
+
+        let mut run_cell = row[0];
+        let mut run_len = 0;
+        for &cell in &row[..=last_alive] {
+            if cell == run_cell {
+                run_len += 1;
+            } else {
+                push_run(&mut tokens, run_len, if run_cell != 0 { 'o' } else { 'b' });
+                run_cell = cell;
+                run_len = 1;
+            }
+        }
+        push_run(&mut tokens, run_len, if run_cell != 0 { 'o' } else { 'b' });
+    }
+    tokens.push("!".to_string());
+
+    let mut out = format!("x = {}, y = {}, rule = B3/S23\n", width, height);
+    let mut line_len = 0;
+    for token in tokens {
+        if line_len + token.len() > LINE_LIMIT {
+            out.push('\n');
+            line_len = 0;
+        }
+        line_len += token.len();
+        out.push_str(&token);
+    }
+    out.push('\n');
+    out
+}
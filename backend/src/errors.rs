@@ -0,0 +1,117 @@
+// backend/src/errors.rs
+use axum::{
+    body::to_bytes,
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{Html, IntoResponse, Json, Response},
+};
+use serde::Serialize;
+
+/// Requests under this prefix get JSON error bodies instead of HTML pages.
+pub const API_PREFIX: &str = "/api/";
+
+/// Largest handler error body read back to build a JSON error message.
+const MAX_ERROR_BODY: usize = 64 * 1024;
+
+/// Renders error responses, as HTML pages from the `templates/error.html`
+/// template or as JSON for API requests.
+///
+/// The template is compiled into the binary rather than kept with the static
+/// files, so its raw `{{status}}`, `{{title}}` and `{{message}}` placeholders
+/// are never served.
+#[derive(Debug, Clone)]
+pub struct ErrorPages {
+    template: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorBody {
+    status: u16,
+    error: String,
+    message: String,
+}
+
+impl Default for ErrorPages {
+    fn default() -> Self {
+        ErrorPages { template: TEMPLATE }
+    }
+}
+
+impl ErrorPages {
+    /// Builds the error response for `path`. `message` is only shown to API
+    /// clients; pages use a generic description of the status.
+    pub fn respond(&self, status: StatusCode, path: &str, message: Option<String>) -> Response {
+        let title = status.canonical_reason().unwrap_or("Error");
+        if path.starts_with(API_PREFIX) {
+            let body = ErrorBody {
+                status: status.as_u16(),
+                error: title.to_string(),
+                message: message.unwrap_or_else(|| describe(status).to_string()),
+            };
+            return (status, Json(body)).into_response();
+        }
+
+        let page = self
+            .template
+            .replace("{{status}}", &status.as_u16().to_string())
+            .replace("{{title}}", title)
+            .replace("{{message}}", describe(status));
+        (status, Html(page)).into_response()
+    }
+}
+
+/// Not-found service for `ServeDir`.
+pub async fn not_found(State(pages): State<ErrorPages>, request: Request) -> Response {
+    pages.respond(StatusCode::NOT_FOUND, request.uri().path(), None)
+}
+
+/// Replaces plain error responses with the error page, or with a JSON error
+/// body under [`API_PREFIX`]. JSON bodies elsewhere, like the readiness
+/// report, are kept as they are. Headers such as `Retry-After` are kept.
+pub async fn error_pages(State(pages): State<ErrorPages>, request: Request, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    let response = next.run(request).await;
+
+    let status = response.status();
+    if !status.is_client_error() && !status.is_server_error() {
+        return response;
+    }
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("");
+    let rendered = content_type.starts_with("application/json")
+        || (!path.starts_with(API_PREFIX) && content_type.starts_with("text/html"));
+    if rendered {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let message = to_bytes(body, MAX_ERROR_BODY)
+        .await
+        .ok()
+        .map(|bytes| String::from_utf8_lossy(&bytes).trim().to_string())
+        .filter(|message| !message.is_empty());
+    let (error_parts, body) = pages.respond(status, &path, message).into_parts();
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    if let Some(content_type) = error_parts.headers.get(header::CONTENT_TYPE) {
+        parts.headers.insert(header::CONTENT_TYPE, content_type.clone());
+    }
+    Response::from_parts(parts, body)
+}
+
+/// Short human-readable explanation of a status.
+fn describe(status: StatusCode) -> &'static str {
+    match status {
+        StatusCode::NOT_FOUND => "The page you are looking for does not exist or has moved.",
+        StatusCode::TOO_MANY_REQUESTS => "Too many requests. Please slow down and try again shortly.",
+        StatusCode::SERVICE_UNAVAILABLE => "The site is temporarily unavailable. Please try again shortly.",
+        s if s.is_server_error() => "Something went wrong on our end. Please try again later.",
+        _ => "The request could not be completed.",
+    }
+}
+
+const TEMPLATE: &str = include_str!("../templates/error.html");
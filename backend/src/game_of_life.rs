@@ -1,9 +1,19 @@
 // backend/src/game_of_life.rs
 use rayon::prelude::*;
+use std::collections::{BTreeMap, VecDeque};
 
 pub const WIDTH: usize = 20;
 pub const HEIGHT: usize = 20;
 
+/// Number of most recent generations kept as full snapshots.
+pub const RETAINED_SNAPSHOTS: usize = 64;
+/// Spacing of the checkpoints kept for generations older than that.
+pub const CHECKPOINT_INTERVAL: usize = 16;
+/// Maximum number of generations the timeline can reach back.
+pub const MAX_TIMELINE: usize = 10_000;
+/// Maximum number of actions that can be undone; older ones are dropped.
+pub const MAX_HISTORY: usize = MAX_TIMELINE;
+
 pub fn index(row: usize, col: usize) -> usize {
     row * WIDTH + col
 }
@@ -41,9 +51,146 @@ pub fn update(current: &[u8], next: &mut [u8]) {
         });
 }
 
+/// Copies the `w`x`h` rectangle whose top-left corner is at column `x`, row
+/// `y`. The caller is responsible for keeping the rectangle on the board.
+pub fn region(grid: &[u8], x: usize, y: usize, w: usize, h: usize) -> Vec<u8> {
+    grid.chunks(WIDTH)
+        .skip(y)
+        .take(h)
+        .flat_map(|row| row[x..x + w].iter().copied())
+        .collect()
+}
+
+/// Downsamples the board into `block`x`block` tiles, returning the fraction
+/// of live cells in each tile along with the tile grid's width and height.
+/// Tiles on the right and bottom edges may be partial.
+pub fn density_blocks(grid: &[u8], block: usize) -> (Vec<f32>, usize, usize) {
+    let cols = WIDTH.div_ceil(block);
+    let rows = HEIGHT.div_ceil(block);
+    let mut densities = Vec::with_capacity(cols * rows);
+    for block_row in 0..rows {
+        for block_col in 0..cols {
+            let y = block_row * block;
+            let x = block_col * block;
+            let h = block.min(HEIGHT - y);
+            let w = block.min(WIDTH - x);
+            let alive: usize = region(grid, x, y, w, h).iter().map(|&c| c as usize).sum();
+            densities.push(alive as f32 / (w * h) as f32);
+        }
+    }
+    (densities, cols, rows)
+}
+
+/// A user-visible action on the board that can be undone and redone.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Step,
+    Toggle { row: usize, col: usize },
+    /// Sets each `(row, col, alive)` cell in one undoable edit.
+    Batch(Vec<(usize, usize, bool)>),
+    Reset,
+    /// Jumps to a generation on the timeline.
+    Seek(usize),
+}
+
+/// An applied action together with the board and generation it replaced.
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub action: Action,
+    pub before: Vec<u8>,
+    pub generation: usize,
+}
+
+/// The run of generations stepped from the last edit, used for scrubbing.
+///
+/// Recent generations are stored in full; older ones are thinned out to
+/// periodic checkpoints and recomputed on demand.
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    origin: usize,
+    latest: usize,
+    checkpoints: BTreeMap<usize, Vec<u8>>,
+}
+
+impl Timeline {
+    pub fn new(generation: usize, board: Vec<u8>) -> Self {
+        let mut checkpoints = BTreeMap::new();
+        checkpoints.insert(generation, board);
+        Self {
+            origin: generation,
+            latest: generation,
+            checkpoints,
+        }
+    }
+
+    /// Earliest generation that can still be reconstructed.
+    pub fn origin(&self) -> usize {
+        self.origin
+    }
+
+    /// Most recent generation recorded.
+    pub fn latest(&self) -> usize {
+        self.latest
+    }
+
+    /// Number of boards stored, full snapshots and checkpoints alike.
+    pub fn stored_boards(&self) -> usize {
+        self.checkpoints.len()
+    }
+
+    pub fn contains(&self, generation: usize) -> bool {
+        generation >= self.origin && generation <= self.latest
+    }
+
+    /// Returns the board for `generation`, recomputing forward from the
+    /// nearest checkpoint if its snapshot was pruned.
+    pub fn board(&self, generation: usize) -> Option<Vec<u8>> {
+        if !self.contains(generation) {
+            return None;
+        }
+        let (&start, snapshot) = self.checkpoints.range(..=generation).next_back()?;
+        let mut board = snapshot.clone();
+        let mut next = vec![0u8; board.len()];
+        for _ in start..generation {
+            update(&board, &mut next);
+            std::mem::swap(&mut board, &mut next);
+        }
+        Some(board)
+    }
+
+    /// Records the board for the generation after `latest`.
+    fn push(&mut self, board: Vec<u8>) {
+        self.latest += 1;
+        self.checkpoints.insert(self.latest, board);
+        self.prune();
+    }
+
+    fn prune(&mut self) {
+        if self.latest - self.origin > MAX_TIMELINE {
+            let cutoff = self.latest - MAX_TIMELINE;
+            if let Some(board) = self.board(cutoff) {
+                self.checkpoints.insert(cutoff, board);
+            }
+            self.checkpoints = self.checkpoints.split_off(&cutoff);
+            self.origin = cutoff;
+        }
+
+        // Thin the generation that just aged out of the full-snapshot window.
+        if let Some(aged) = self.latest.checked_sub(RETAINED_SNAPSHOTS)
+            && aged > self.origin
+            && aged % CHECKPOINT_INTERVAL != 0
+        {
+            self.checkpoints.remove(&aged);
+        }
+    }
+}
+
 pub struct GameOfLife {
     pub current: Vec<u8>,
-    pub history: Vec<Vec<u8>>,
+    pub generation: usize,
+    pub history: VecDeque<HistoryEntry>,
+    pub redo: Vec<HistoryEntry>,
+    pub timeline: Timeline,
     pub initial: Vec<u8>,
 }
 
@@ -51,8 +198,11 @@ impl GameOfLife {
     pub fn new(initial: Vec<u8>) -> Self {
         Self {
             current: initial.clone(),
+            generation: 0,
+            timeline: Timeline::new(0, initial.clone()),
             initial,
-            history: Vec::new(),
+            history: VecDeque::new(),
+            redo: Vec::new(),
         }
     }
 
@@ -61,31 +211,140 @@ impl GameOfLife {
     }
 
     pub fn step(&mut self) {
-        self.history.push(self.current.clone());
-        let mut next = vec![0u8; WIDTH * HEIGHT];
-        update(&self.current, &mut next);
-        self.current = next;
+        self.perform(Action::Step);
     }
 
+    /// Kept for the `/back` endpoint; equivalent to [`GameOfLife::undo`].
     pub fn step_back(&mut self) -> bool {
-        if let Some(prev) = self.history.pop() {
-            self.current = prev;
+        self.undo()
+    }
+
+    pub fn toggle_cell(&mut self, row: usize, col: usize) {
+        if row < HEIGHT && col < WIDTH {
+            self.perform(Action::Toggle { row, col });
+        }
+    }
+
+    /// Sets several cells at once as a single undoable edit. Out-of-bounds
+    /// cells are ignored.
+    pub fn set_cells(&mut self, cells: &[(usize, usize, bool)]) {
+        let cells: Vec<_> = cells
+            .iter()
+            .copied()
+            .filter(|&(row, col, _)| row < HEIGHT && col < WIDTH)
+            .collect();
+        if !cells.is_empty() {
+            self.perform(Action::Batch(cells));
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.perform(Action::Reset);
+    }
+
+    /// Returns the board for a generation on the timeline, if retained.
+    pub fn generation_board(&self, generation: usize) -> Option<Vec<u8>> {
+        self.timeline.board(generation)
+    }
+
+    /// Moves the current state to `generation` as an undoable action.
+    /// Returns `false` if the generation is not on the timeline.
+    pub fn seek(&mut self, generation: usize) -> bool {
+        if !self.timeline.contains(generation) {
+            return false;
+        }
+        if generation != self.generation {
+            self.perform(Action::Seek(generation));
+        }
+        true
+    }
+
+    /// Reverts the most recent action. Returns `false` if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        if let Some(entry) = self.history.pop_back() {
+            let after = std::mem::replace(&mut self.current, entry.before);
+            let generation = std::mem::replace(&mut self.generation, entry.generation);
+            self.redo.push(HistoryEntry { action: entry.action, before: after, generation });
+            self.sync_timeline();
             true
         } else {
             false
         }
     }
 
-    pub fn toggle_cell(&mut self, row: usize, col: usize) {
-        if row < HEIGHT && col < WIDTH {
-            let idx = index(row, col);
-            self.current[idx] = if self.current[idx] == 0 { 1 } else { 0 };
+    /// Re-applies the most recently undone action. Returns `false` if there is
+    /// nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        if let Some(entry) = self.redo.pop() {
+            let before = std::mem::replace(&mut self.current, entry.before);
+            let generation = std::mem::replace(&mut self.generation, entry.generation);
+            self.record(HistoryEntry { action: entry.action, before, generation });
+            self.sync_timeline();
+            true
+        } else {
+            false
         }
     }
 
-    pub fn reset(&mut self) {
-        self.current = self.initial.clone();
-        self.history.clear();
+    /// Applies a new action, recording it for undo and invalidating redo.
+    fn perform(&mut self, action: Action) {
+        let before = self.current.clone();
+        let generation = self.generation;
+        self.apply(&action);
+        self.record(HistoryEntry { action, before, generation });
+        self.redo.clear();
+    }
+
+    /// Pushes an undo entry, dropping the oldest beyond [`MAX_HISTORY`].
+    fn record(&mut self, entry: HistoryEntry) {
+        if self.history.len() == MAX_HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back(entry);
+    }
+
+    fn apply(&mut self, action: &Action) {
+        match action {
+            Action::Step => {
+                let mut next = vec![0u8; WIDTH * HEIGHT];
+                update(&self.current, &mut next);
+                self.current = next;
+                self.generation += 1;
+                if self.generation > self.timeline.latest() {
+                    self.timeline.push(self.current.clone());
+                }
+            }
+            Action::Toggle { row, col } => {
+                let idx = index(*row, *col);
+                self.current[idx] = if self.current[idx] == 0 { 1 } else { 0 };
+                self.timeline = Timeline::new(self.generation, self.current.clone());
+            }
+            Action::Batch(cells) => {
+                for &(row, col, alive) in cells {
+                    self.current[index(row, col)] = alive as u8;
+                }
+                self.timeline = Timeline::new(self.generation, self.current.clone());
+            }
+            Action::Reset => {
+                self.current = self.initial.clone();
+                self.generation = 0;
+                self.timeline = Timeline::new(0, self.current.clone());
+            }
+            Action::Seek(generation) => {
+                if let Some(board) = self.timeline.board(*generation) {
+                    self.current = board;
+                    self.generation = *generation;
+                }
+            }
+        }
+    }
+
+    /// Restarts the timeline at the current board if undo/redo moved it off
+    /// the recorded run.
+    fn sync_timeline(&mut self) {
+        if self.timeline.board(self.generation).as_deref() != Some(&self.current[..]) {
+            self.timeline = Timeline::new(self.generation, self.current.clone());
+        }
     }
 }
 
@@ -0,0 +1,214 @@
+// backend/src/health.rs
+use crate::game_of_life::GameOfLife;
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, TryLockError};
+use std::time::{Duration, Instant};
+
+/// How long `/readyz` waits for the game state lock before reporting it
+/// unavailable.
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_millis(250);
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Build information reported by both endpoints.
+#[derive(Debug, Serialize)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    /// From `GIT_COMMIT` at compile time, if it was set.
+    pub commit: &'static str,
+}
+
+impl BuildInfo {
+    pub fn current() -> Self {
+        BuildInfo {
+            version: env!("CARGO_PKG_VERSION"),
+            commit: option_env!("GIT_COMMIT").unwrap_or("unknown"),
+        }
+    }
+}
+
+/// The outcome of one readiness check.
+#[derive(Debug, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    /// `ok`, or `unavailable` if any check failed.
+    pub status: &'static str,
+    #[serde(flatten)]
+    pub build: BuildInfo,
+    pub uptime_seconds: f64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub checks: Vec<Check>,
+}
+
+/// What `/readyz` checks before the server is considered ready.
+pub struct Health {
+    started: Instant,
+    static_dir: PathBuf,
+    log_files: Vec<PathBuf>,
+    game: Option<Arc<Mutex<GameOfLife>>>,
+    pub lock_timeout: Duration,
+}
+
+impl Health {
+    /// Checks `static_dir` and that logging is enabled.
+    pub fn new(static_dir: impl Into<PathBuf>) -> Self {
+        Health {
+            started: Instant::now(),
+            static_dir: static_dir.into(),
+            log_files: Vec::new(),
+            game: None,
+            lock_timeout: DEFAULT_LOCK_TIMEOUT,
+        }
+    }
+
+    /// Also checks that the game state lock can be acquired.
+    pub fn with_game(mut self, game: Arc<Mutex<GameOfLife>>) -> Self {
+        self.game = Some(game);
+        self
+    }
+
+    /// Also checks that these log files are writable, typically those from
+    /// [`appender_paths`].
+    pub fn log_files(mut self, files: impl IntoIterator<Item = impl Into<PathBuf>>) -> Self {
+        self.log_files = files.into_iter().map(Into::into).collect();
+        self
+    }
+
+    fn report(&self, checks: Vec<Check>) -> HealthReport {
+        HealthReport {
+            status: if checks.iter().all(|check| check.ok) { "ok" } else { "unavailable" },
+            build: BuildInfo::current(),
+            uptime_seconds: self.started.elapsed().as_secs_f64(),
+            checks,
+        }
+    }
+
+    /// Runs every readiness check.
+    pub async fn check(&self) -> HealthReport {
+        let (static_dir, log_files) = (self.static_dir.clone(), self.log_files.clone());
+        let mut checks = tokio::task::spawn_blocking(move || {
+            vec![
+                timed("static_dir", || check_static_dir(&static_dir)),
+                timed("logging", || check_logging(&log_files)),
+            ]
+        })
+        .await
+        .unwrap_or_else(|e| vec![Check::new("filesystem", Err(e.to_string()), Instant::now())]);
+        if let Some(game) = &self.game {
+            let start = Instant::now();
+            let result = lock_within(game, self.lock_timeout).await;
+            checks.push(Check::new("game_state", result, start));
+        }
+        self.report(checks)
+    }
+}
+
+impl Check {
+    fn new(name: &'static str, result: Result<(), String>, start: Instant) -> Self {
+        Check {
+            name,
+            ok: result.is_ok(),
+            error: result.err(),
+            duration_ms: start.elapsed().as_secs_f64() * 1000.0,
+        }
+    }
+}
+
+fn timed(name: &'static str, check: impl FnOnce() -> Result<(), String>) -> Check {
+    let start = Instant::now();
+    Check::new(name, check(), start)
+}
+
+fn check_static_dir(dir: &Path) -> Result<(), String> {
+    std::fs::read_dir(dir)
+        .map(|_| ())
+        .map_err(|e| format!("{} is not readable: {}", dir.display(), e))
+}
+
+/// The logger must be installed, and each log file must exist and be
+/// writable. The appenders create their files at startup, so a missing one
+/// means it was removed or never opened.
+fn check_logging(files: &[PathBuf]) -> Result<(), String> {
+    if log::max_level() == log::LevelFilter::Off {
+        return Err("logging is disabled".to_string());
+    }
+    for file in files {
+        OpenOptions::new()
+            .append(true)
+            .open(file)
+            .map_err(|e| format!("{} is not writable: {}", file.display(), e))?;
+    }
+    log::logger().flush();
+    Ok(())
+}
+
+/// Paths written by the file appenders in a log4rs YAML config, such as the
+/// `log4rs.yaml` the server initializes logging from.
+pub fn appender_paths(config: &Path) -> Result<Vec<PathBuf>, String> {
+    let text = std::fs::read_to_string(config).map_err(|e| format!("{}: {}", config.display(), e))?;
+    let yaml: serde_yaml::Value = serde_yaml::from_str(&text).map_err(|e| format!("{}: {}", config.display(), e))?;
+    Ok(yaml
+        .get("appenders")
+        .and_then(serde_yaml::Value::as_mapping)
+        .into_iter()
+        .flat_map(|appenders| appenders.values())
+        .filter_map(|appender| appender.get("path")?.as_str())
+        .map(PathBuf::from)
+        .collect())
+}
+
+/// Polls the lock rather than blocking, so a stuck holder makes the check
+/// fail instead of hanging the probe.
+async fn lock_within(game: &Mutex<GameOfLife>, timeout: Duration) -> Result<(), String> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match game.try_lock() {
+            Ok(_) => return Ok(()),
+            Err(TryLockError::Poisoned(_)) => return Err("game state lock is poisoned".to_string()),
+            Err(TryLockError::WouldBlock) => {}
+        }
+        if Instant::now() >= deadline {
+            return Err(format!("game state lock not acquired within {}ms", timeout.as_millis()));
+        }
+        tokio::time::sleep(LOCK_RETRY_INTERVAL).await;
+    }
+}
+
+/// Liveness: the server is up and answering requests.
+pub async fn healthz(State(health): State<Arc<Health>>) -> Json<HealthReport> {
+    Json(health.report(Vec::new()))
+}
+
+/// Readiness: `200` if every check passes, otherwise `503` listing the
+/// failures.
+pub async fn readyz(State(health): State<Arc<Health>>) -> Response {
+    let report = health.check().await;
+    if report.status != "ok" {
+        log::warn!(
+            "Readiness check failed: {}",
+            report
+                .checks
+                .iter()
+                .filter_map(|check| check.error.as_deref())
+                .collect::<Vec<_>>()
+                .join("; ")
+        );
+    }
+    let status = if report.status == "ok" { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(report)).into_response()
+}
@@ -1,40 +1,78 @@
 pub mod game_of_life;
 pub mod routes;
 pub mod middleware;
+pub mod encoding;
+pub mod render;
+pub mod sitemap;
+pub mod errors;
+pub mod cache;
+pub mod security;
+pub mod rate_limit;
+pub mod client_ip;
+pub mod metrics;
+pub mod health;
 
 use axum::{
     Router,
     routing::{get_service, get},
-    middleware::from_fn,
+    middleware::from_fn_with_state,
+    handler::Handler,
 };
 
-use std::path::PathBuf;
+use axum::extract::Extension;
+use cache::CachePolicy;
+use errors::ErrorPages;
+use client_ip::TrustedProxies;
+use health::Health;
+use metrics::Metrics;
+use middleware::AccessLog;
+use rate_limit::RateLimiter;
+use security::SecurityHeaders;
+use sitemap::Sitemap;
+use std::sync::Arc;
+use tower_http::compression::CompressionLayer;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
 use tower_http::services::ServeDir;
-use axum::http::{StatusCode, Uri};
-use axum::response::{Html, IntoResponse};
-use tokio::fs::read_to_string;
-
-/// Fallback handler for 404 errors.
-#[allow(dead_code)]
-async fn fallback_handler(uri: Uri) -> impl IntoResponse {
-    let path = PathBuf::from("static/404.html");
-    let html = match read_to_string(path).await {
-        Ok(content) => Html(content),
-        Err(_) => Html(format!("<h1>404 Not Found</h1><p>No page found for {}</p>", uri)),
-    };
-    (StatusCode::NOT_FOUND, html)
-}
 
 /// Builds the application router.
 /// - `static_dir`: the directory for static assets.
 /// - `game_api`: the router for your Game of Life API endpoints.
-pub fn app(static_dir: &str, game_api: Router) -> Router {
-    let static_service = get_service(ServeDir::new(static_dir));
+/// - `sitemap`: the sitemap served at `/sitemap.xml`.
+/// - `metrics`: the metrics recorded for every request and served at `/metrics`.
+/// - `health`: the checks behind `/healthz` and `/readyz`.
+/// - `rate_limiter`: the per-client limits applied to every route.
+pub fn app(
+    static_dir: &str,
+    game_api: Router,
+    sitemap: Sitemap,
+    metrics: Metrics,
+    health: Health,
+    rate_limiter: RateLimiter,
+) -> Router {
+    let health = Arc::new(health);
+    let error_pages = ErrorPages::default();
+    let not_found = errors::not_found.with_state(error_pages.clone());
+    let static_files = ServeDir::new(static_dir)
+        .precompressed_br()
+        .precompressed_gzip()
+        .not_found_service(not_found);
+    let static_service = get_service(static_files)
+        .layer(from_fn_with_state(CachePolicy::default(), cache::static_headers));
 
     Router::new()
-        .route("/sitemap.xml", get(routes::sitemap))
+        .route("/sitemap.xml", get(routes::sitemap).layer(Extension(sitemap)))
+        .route("/healthz", get(health::healthz).with_state(health.clone()))
+        .route("/readyz", get(health::readyz).with_state(health))
+        .route("/metrics", get(metrics::metrics_handler).with_state(metrics.clone()))
         .nest("/api/game-of-life", game_api)
         .fallback_service(static_service)
-        .layer(from_fn(middleware::log_requests))
+        .layer(from_fn_with_state(Arc::new(rate_limiter), rate_limit::rate_limit))
+        .layer(from_fn_with_state(error_pages, errors::error_pages))
+        .layer(CompressionLayer::new().gzip(true).br(true).deflate(false).zstd(false))
+        .layer(from_fn_with_state(Arc::new(SecurityHeaders::default()), security::security_headers))
+        .layer(from_fn_with_state(metrics, metrics::track_requests))
+        .layer(from_fn_with_state(Arc::new(AccessLog::from_env()), middleware::log_requests))
+        .layer(from_fn_with_state(Arc::new(TrustedProxies::from_env()), client_ip::resolve_client_ip))
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
 }
-
@@ -1,16 +1,24 @@
 use std::net::SocketAddr;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 use tokio::net::TcpListener;
 use backend::game_of_life::{GameOfLife, parse_initial_state};
 use backend::routes::game_api;
 use backend::app;
-use log::info;
+use backend::health::{self, Health};
+use backend::metrics::Metrics;
+use backend::rate_limit::RateLimiter;
+use backend::sitemap::{Sitemap, SitemapConfig, REFRESH_INTERVAL};
+use log::{info, warn};
 use log4rs;
 
+/// Logging configuration, also read for the log files `/readyz` checks.
+const LOG_CONFIG: &str = "log4rs.yaml";
+
 #[tokio::main]
 async fn main() {
 
-    log4rs::init_file("log4rs.yaml", Default::default())
+    log4rs::init_file(LOG_CONFIG, Default::default())
         .expect("Failed to initialize log4rs");
 
     info!("Starting the portfolio server...");
@@ -40,8 +48,16 @@ async fn main() {
     let initial_grid = parse_initial_state(&initial_state);
     let game_state = Arc::new(Mutex::new(GameOfLife::new(initial_grid)));
 
+    let metrics = Metrics::new(game_state.clone());
+    let log_files = health::appender_paths(Path::new(LOG_CONFIG)).unwrap_or_else(|e| {
+        warn!("Not checking log files: {}", e);
+        Vec::new()
+    });
+    let health = Health::new("static").with_game(game_state.clone()).log_files(log_files);
     let api = game_api().layer(axum::extract::Extension(game_state));
-    let app = app("static", api);
+    let sitemap = Sitemap::new("static", SitemapConfig::from_env());
+    sitemap.watch(REFRESH_INTERVAL);
+    let app = app("static", api, sitemap, metrics, health, RateLimiter::from_env());
 
     let addr = SocketAddr::from(([0, 0, 0, 0], 8086));
     println!("Serving portfolio at http://{}", addr);
@@ -0,0 +1,331 @@
+// backend/src/metrics.rs
+use crate::client_ip::ClientIp;
+use crate::game_of_life::GameOfLife;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Upper bounds of the request latency histogram buckets, in seconds.
+pub const LATENCY_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+/// Window over which `game_of_life_steps_per_second` is averaged.
+pub const STEP_RATE_WINDOW: Duration = Duration::from_secs(60);
+
+const STEP_ROUTE: &str = "/api/game-of-life/step";
+
+#[derive(Debug, Default)]
+struct RouteStats {
+    count: u64,
+    /// Non-cumulative counts per bucket; the last slot is `+Inf`.
+    buckets: [u64; LATENCY_BUCKETS.len() + 1],
+    sum_seconds: f64,
+}
+
+struct Inner {
+    started: Instant,
+    start_time: SystemTime,
+    localhost_only: bool,
+    game: Option<Arc<Mutex<GameOfLife>>>,
+    in_flight: AtomicI64,
+    requests: Mutex<BTreeMap<(String, String, u16), RouteStats>>,
+    steps_total: AtomicU64,
+    recent_steps: Mutex<VecDeque<Instant>>,
+}
+
+/// Request, game and process metrics, served in the Prometheus text format.
+///
+/// Cheap to clone; clones share the same counters.
+#[derive(Clone)]
+pub struct Metrics {
+    inner: Arc<Inner>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics {
+            inner: Arc::new(Inner {
+                started: Instant::now(),
+                start_time: SystemTime::now(),
+                localhost_only: true,
+                game: None,
+                in_flight: AtomicI64::new(0),
+                requests: Mutex::new(BTreeMap::new()),
+                steps_total: AtomicU64::new(0),
+                recent_steps: Mutex::new(VecDeque::new()),
+            }),
+        }
+    }
+}
+
+impl Metrics {
+    /// Metrics that also report on `game`. The endpoint only answers local
+    /// clients unless `METRICS_LOCALHOST_ONLY=false`.
+    pub fn new(game: Arc<Mutex<GameOfLife>>) -> Self {
+        let localhost_only = !std::env::var("METRICS_LOCALHOST_ONLY")
+            .is_ok_and(|value| matches!(value.to_ascii_lowercase().as_str(), "0" | "false" | "no"));
+        Metrics::default().with_game(game).localhost_only(localhost_only)
+    }
+
+    /// Reports generation, population and history of `game`.
+    pub fn with_game(self, game: Arc<Mutex<GameOfLife>>) -> Self {
+        self.rebuild(|inner| inner.game = Some(game))
+    }
+
+    /// Whether `/metrics` only answers clients on the loopback interface, as
+    /// it does by default.
+    pub fn localhost_only(self, localhost_only: bool) -> Self {
+        self.rebuild(|inner| inner.localhost_only = localhost_only)
+    }
+
+    /// Applies configuration before any clones share the counters.
+    fn rebuild(self, configure: impl FnOnce(&mut Inner)) -> Self {
+        let Ok(mut inner) = Arc::try_unwrap(self.inner) else {
+            panic!("Metrics configured after being shared");
+        };
+        configure(&mut inner);
+        Metrics { inner: Arc::new(inner) }
+    }
+
+    fn record(&self, method: &Method, route: &str, status: StatusCode, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|&bound| seconds <= bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        let key = (route.to_string(), method_label(method).to_string(), status.as_u16());
+        let mut requests = self.inner.requests.lock().unwrap();
+        let stats = requests.entry(key).or_default();
+        stats.count += 1;
+        stats.buckets[bucket] += 1;
+        stats.sum_seconds += seconds;
+        drop(requests);
+
+        if route == STEP_ROUTE && method == Method::POST && status.is_success() {
+            self.inner.steps_total.fetch_add(1, Ordering::Relaxed);
+            let now = Instant::now();
+            let mut recent = self.inner.recent_steps.lock().unwrap();
+            prune_steps(&mut recent, now);
+            recent.push_back(now);
+        }
+    }
+
+    fn steps_per_second(&self) -> f64 {
+        let now = Instant::now();
+        let mut recent = self.inner.recent_steps.lock().unwrap();
+        prune_steps(&mut recent, now);
+        let window = STEP_RATE_WINDOW.min(now.duration_since(self.inner.started)).as_secs_f64();
+        if window > 0.0 { recent.len() as f64 / window } else { 0.0 }
+    }
+
+    /// Renders every metric in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        self.render_requests(&mut out);
+        self.render_game(&mut out);
+        self.render_process(&mut out);
+        out
+    }
+
+    fn render_requests(&self, out: &mut String) {
+        let requests = self.inner.requests.lock().unwrap();
+
+        metric_header(out, "http_requests_total", "counter", "Total HTTP requests by route, method and status.");
+        for ((route, method, status), stats) in requests.iter() {
+            let _ = writeln!(out, "http_requests_total{} {}", labels(route, method, *status, None), stats.count);
+        }
+
+        metric_header(
+            out,
+            "http_request_duration_seconds",
+            "histogram",
+            "Time until response headers were ready, by route, method and status.",
+        );
+        for ((route, method, status), stats) in requests.iter() {
+            let mut cumulative = 0;
+            for (i, count) in stats.buckets.iter().enumerate() {
+                cumulative += count;
+                let le = LATENCY_BUCKETS.get(i).map_or("+Inf".to_string(), |bound| bound.to_string());
+                let _ = writeln!(
+                    out,
+                    "http_request_duration_seconds_bucket{} {}",
+                    labels(route, method, *status, Some(&le)),
+                    cumulative
+                );
+            }
+            let series = labels(route, method, *status, None);
+            let _ = writeln!(out, "http_request_duration_seconds_sum{} {}", series, stats.sum_seconds);
+            let _ = writeln!(out, "http_request_duration_seconds_count{} {}", series, stats.count);
+        }
+
+        metric_header(out, "http_requests_in_flight", "gauge", "Requests currently being handled.");
+        let _ = writeln!(out, "http_requests_in_flight {}", self.inner.in_flight.load(Ordering::Relaxed));
+    }
+
+    fn render_game(&self, out: &mut String) {
+        if let Some(game) = &self.inner.game
+            && let Ok(game) = game.lock()
+        {
+            let population = game.current.iter().filter(|&&cell| cell != 0).count();
+            for (name, help, value) in [
+                ("game_of_life_generation", "Generation of the shared board.", game.generation),
+                ("game_of_life_population", "Live cells on the shared board.", population),
+                ("game_of_life_history_length", "Actions that can be undone.", game.history.len()),
+                ("game_of_life_redo_length", "Actions that can be redone.", game.redo.len()),
+            ] {
+                metric_header(out, name, "gauge", help);
+                let _ = writeln!(out, "{} {}", name, value);
+            }
+        }
+
+        metric_header(out, "game_of_life_steps_total", "counter", "Steps requested through the API.");
+        let _ = writeln!(out, "game_of_life_steps_total {}", self.inner.steps_total.load(Ordering::Relaxed));
+        metric_header(
+            out,
+            "game_of_life_steps_per_second",
+            "gauge",
+            "Steps requested per second over the last minute.",
+        );
+        let _ = writeln!(out, "game_of_life_steps_per_second {}", self.steps_per_second());
+    }
+
+    fn render_process(&self, out: &mut String) {
+        let start = self.inner.start_time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        metric_header(out, "process_start_time_seconds", "gauge", "Start time of the process since the Unix epoch.");
+        let _ = writeln!(out, "process_start_time_seconds {}", start.as_secs_f64());
+        metric_header(out, "process_uptime_seconds", "gauge", "Seconds since the process started.");
+        let _ = writeln!(out, "process_uptime_seconds {}", self.inner.started.elapsed().as_secs_f64());
+
+        // Linux only; these are simply left out elsewhere.
+        if let Ok(stat) = std::fs::read_to_string("/proc/self/stat")
+            && let Some((_, fields)) = stat.rsplit_once(')')
+        {
+            // Fields after the command name, starting from field 3 (state).
+            let fields: Vec<&str> = fields.split_whitespace().collect();
+            let field = |n: usize| fields.get(n - 3).and_then(|value| value.parse::<u64>().ok());
+            if let (Some(utime), Some(stime)) = (field(14), field(15)) {
+                metric_header(out, "process_cpu_seconds_total", "counter", "User and system CPU time spent.");
+                let _ = writeln!(out, "process_cpu_seconds_total {}", (utime + stime) as f64 / CLOCK_TICKS_PER_SECOND);
+            }
+            if let Some(threads) = field(20) {
+                metric_header(out, "process_threads", "gauge", "Number of OS threads.");
+                let _ = writeln!(out, "process_threads {}", threads);
+            }
+            if let Some(virtual_bytes) = field(23) {
+                metric_header(out, "process_virtual_memory_bytes", "gauge", "Virtual memory size in bytes.");
+                let _ = writeln!(out, "process_virtual_memory_bytes {}", virtual_bytes);
+            }
+        }
+        if let Ok(status) = std::fs::read_to_string("/proc/self/status")
+            && let Some(rss_kb) = status
+                .lines()
+                .find_map(|line| line.strip_prefix("VmRSS:"))
+                .and_then(|value| value.trim().trim_end_matches("kB").trim().parse::<u64>().ok())
+        {
+            metric_header(out, "process_resident_memory_bytes", "gauge", "Resident memory size in bytes.");
+            let _ = writeln!(out, "process_resident_memory_bytes {}", rss_kb * 1024);
+        }
+        if let Ok(fds) = std::fs::read_dir("/proc/self/fd") {
+            metric_header(out, "process_open_fds", "gauge", "Number of open file descriptors.");
+            let _ = writeln!(out, "process_open_fds {}", fds.count());
+        }
+    }
+}
+
+/// Drops steps that have left the rate window.
+fn prune_steps(recent: &mut VecDeque<Instant>, now: Instant) {
+    while recent.front().is_some_and(|&at| now.duration_since(at) > STEP_RATE_WINDOW) {
+        recent.pop_front();
+    }
+}
+
+/// Label for a request method. Clients can send any token as a method, so
+/// anything nonstandard shares `other` rather than adding a series each.
+fn method_label(method: &Method) -> &'static str {
+    match *method {
+        Method::GET => "GET",
+        Method::HEAD => "HEAD",
+        Method::POST => "POST",
+        Method::PUT => "PUT",
+        Method::DELETE => "DELETE",
+        Method::CONNECT => "CONNECT",
+        Method::OPTIONS => "OPTIONS",
+        Method::TRACE => "TRACE",
+        Method::PATCH => "PATCH",
+        _ => "other",
+    }
+}
+
+/// `USER_HZ`, which is 100 on every mainstream Linux platform.
+const CLOCK_TICKS_PER_SECOND: f64 = 100.0;
+
+fn metric_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn labels(route: &str, method: &str, status: u16, le: Option<&str>) -> String {
+    let mut labels = format!(
+        "{{route=\"{}\",method=\"{}\",status=\"{}\"",
+        escape_label(route),
+        escape_label(method),
+        status
+    );
+    if let Some(le) = le {
+        let _ = write!(labels, ",le=\"{}\"", le);
+    }
+    labels.push('}');
+    labels
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Decrements the in-flight gauge even if the request is cancelled.
+struct InFlight<'a>(&'a AtomicI64);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Counts and times every request. Requests are labelled with their route
+/// pattern, so `/generation/5` and `/generation/6` share a series; static
+/// files share `static` and unknown API paths share `unmatched`.
+pub async fn track_requests(State(metrics): State<Metrics>, request: Request, next: Next) -> Response {
+    let route = match request.extensions().get::<MatchedPath>() {
+        Some(matched) => matched.as_str().to_string(),
+        None if request.uri().path().starts_with("/api/") => "unmatched".to_string(),
+        None => "static".to_string(),
+    };
+    let method = request.method().clone();
+
+    metrics.inner.in_flight.fetch_add(1, Ordering::Relaxed);
+    let _in_flight = InFlight(&metrics.inner.in_flight);
+    let start = Instant::now();
+    let response = next.run(request).await;
+    metrics.record(&method, &route, response.status(), start.elapsed());
+    response
+}
+
+/// Serves the metrics, refusing non-local clients when restricted.
+pub async fn metrics_handler(State(metrics): State<Metrics>, request: Request) -> Response {
+    if metrics.inner.localhost_only
+        && !ClientIp::from_request(&request).is_some_and(|ip| ip.to_canonical().is_loopback())
+    {
+        return (StatusCode::FORBIDDEN, "Metrics are only available locally").into_response();
+    }
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        metrics.render(),
+    )
+        .into_response()
+}
@@ -1,52 +1,377 @@
 use axum::{
-    extract::ConnectInfo,
-    http::{Request, HeaderMap},
+    extract::{ConnectInfo, State},
+    http::{header, Request, HeaderMap},
     middleware::Next,
-    response::IntoResponse,
-    body::Body,
+    response::{IntoResponse, Response},
+    body::{Body, Bytes},
 };
+use http_body::{Body as HttpBody, Frame, SizeHint};
+use serde::Serialize;
+use std::future::Future;
 use std::net::SocketAddr;
-use std::time::Instant;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use crate::cache::CacheStatus;
+use crate::client_ip::ClientIp;
+use std::time::{Duration, Instant};
+use tower_http::request_id::RequestId;
+
+/// Key under which the request id is placed in the log MDC, so log4rs
+/// patterns can include it with `{X(request_id)}`.
+pub const REQUEST_ID_KEY: &str = "request_id";
+
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Log target of the access log. Only [`AccessRecord`] lines are written
+/// here: the appender adds no timestamp of its own, and the JSON and
+/// combined streams must stay one record per line.
+const ACCESS_TARGET: &str = "access";
+
+/// Layout of each line written to the `access` log target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AccessLogFormat {
+    /// The original space-separated line, unchanged so existing parsers keep
+    /// working, followed by `key=value` columns for the transfer details:
+    /// `bytes_sent`, `ttfb_us`, `latency_us` and `cache`, then
+    /// `precompressed` and `aborted` when set. Every format carries its own
+    /// timestamp, so the `access` appender's log4rs pattern should be just
+    /// `{m}{n}`.
+    #[default]
+    Text,
+    /// One JSON object per request.
+    Json,
+    /// Apache/NCSA Combined Log Format, readable by tools like GoAccess.
+    Combined,
+}
+
+impl AccessLogFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "text" => Some(AccessLogFormat::Text),
+            "json" => Some(AccessLogFormat::Json),
+            "combined" | "clf" => Some(AccessLogFormat::Combined),
+            _ => None,
+        }
+    }
+
+    /// Reads `ACCESS_LOG_FORMAT`, defaulting to [`AccessLogFormat::Text`].
+    pub fn from_env() -> Self {
+        match std::env::var("ACCESS_LOG_FORMAT") {
+            Ok(name) => AccessLogFormat::from_name(&name).unwrap_or_else(|| {
+                log::warn!("Unknown ACCESS_LOG_FORMAT {}, using text", name);
+                AccessLogFormat::default()
+            }),
+            Err(_) => AccessLogFormat::default(),
+        }
+    }
+}
+
+/// Access log settings.
+#[derive(Debug, Clone)]
+pub struct AccessLog {
+    pub format: AccessLogFormat,
+    /// Paths whose requests are not logged, such as the health probes that
+    /// would otherwise fill the log every few seconds.
+    pub exclude: Vec<String>,
+}
+
+impl Default for AccessLog {
+    fn default() -> Self {
+        AccessLog {
+            format: AccessLogFormat::default(),
+            exclude: vec!["/healthz".to_string(), "/readyz".to_string()],
+        }
+    }
+}
+
+impl AccessLog {
+    /// Reads `ACCESS_LOG_FORMAT`, and `ACCESS_LOG_EXCLUDE` as a
+    /// comma-separated list of paths. An empty `ACCESS_LOG_EXCLUDE` logs
+    /// every request.
+    pub fn from_env() -> Self {
+        let mut config = AccessLog {
+            format: AccessLogFormat::from_env(),
+            ..AccessLog::default()
+        };
+        if let Ok(paths) = std::env::var("ACCESS_LOG_EXCLUDE") {
+            config.exclude = paths
+                .split(',')
+                .map(str::trim)
+                .filter(|path| !path.is_empty())
+                .map(String::from)
+                .collect();
+        }
+        config
+    }
+
+    fn is_excluded(&self, path: &str) -> bool {
+        self.exclude.iter().any(|excluded| excluded == path)
+    }
+}
+
+/// Everything recorded about one request.
+#[derive(Debug, Serialize)]
+pub struct AccessRecord {
+    pub timestamp: String,
+    pub request_id: String,
+    pub ip: String,
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+    pub protocol: String,
+    pub status: u16,
+    /// Body bytes actually streamed to the client.
+    pub bytes_sent: u64,
+    /// Time until the first body byte was ready, or until the body ended if
+    /// it was empty.
+    pub ttfb_us: u64,
+    /// Time until the body finished streaming.
+    pub latency_us: u64,
+    pub user_agent: String,
+    pub referer: String,
+    /// `hit` when a static file was answered with `304 Not Modified`,
+    /// `miss` when it was sent; absent for other responses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache: Option<&'static str>,
+    /// Whether a precompressed `.br`/`.gz` static file was served.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub precompressed: bool,
+    /// Whether the client went away before the body finished.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub aborted: bool,
+}
+
+impl AccessRecord {
+    /// Renders the record as a single log line.
+    pub fn format(&self, format: AccessLogFormat) -> String {
+        match format {
+            AccessLogFormat::Text => {
+                // log4rs's default `{d}`, which this line was prefixed with
+                // before each format carried its own timestamp
+                let time = chrono::DateTime::parse_from_rfc3339(&self.timestamp)
+                    .map(|time| time.with_timezone(&chrono::Local).format("%+").to_string())
+                    .unwrap_or_else(|_| self.timestamp.clone());
+                let mut line = format!(
+                    "[{}] {} {} {}{} {} \"{}\" \"{}\" {}ms bytes_sent={} ttfb_us={} latency_us={} cache={}",
+                    time,
+                    self.ip,
+                    self.method,
+                    self.path,
+                    self.query.as_ref().map(|q| format!("?{}", q)).unwrap_or_default(),
+                    self.status,
+                    self.user_agent,
+                    self.referer,
+                    self.latency_us / 1000,
+                    self.bytes_sent,
+                    self.ttfb_us,
+                    self.latency_us,
+                    self.cache.unwrap_or("-")
+                );
+                if self.precompressed {
+                    line.push_str(" precompressed=true");
+                }
+                if self.aborted {
+                    line.push_str(" aborted=true");
+                }
+                line
+            }
+            AccessLogFormat::Json => serde_json::to_string(self).unwrap_or_default(),
+            AccessLogFormat::Combined => {
+                let time = chrono::DateTime::parse_from_rfc3339(&self.timestamp)
+                    .map(|time| time.format("%d/%b/%Y:%H:%M:%S %z").to_string())
+                    .unwrap_or_else(|_| self.timestamp.clone());
+                format!(
+                    "{} - - [{}] \"{} {}{} {}\" {} {} \"{}\" \"{}\"",
+                    self.ip,
+                    time,
+                    self.method,
+                    self.path,
+                    self.query.as_ref().map(|q| format!("?{}", q)).unwrap_or_default(),
+                    self.protocol,
+                    self.status,
+                    if self.bytes_sent == 0 { "-".to_string() } else { self.bytes_sent.to_string() },
+                    escape_quotes(&self.referer),
+                    escape_quotes(&self.user_agent)
+                )
+            }
+        }
+    }
+}
 
 /// HTTP request logging middleware that captures comprehensive request/response data
 pub async fn log_requests(
+    State(config): State<Arc<AccessLog>>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     request: Request<Body>,
     next: Next,
 ) -> impl IntoResponse {
     let start = Instant::now();
-    
+    let format = config.format;
+    let timestamp = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+
+    // Prefer the address resolved from trusted proxy headers
+    let client_ip = ClientIp::from_request(&request).unwrap_or(addr.ip());
+
     // Extract request information
     let method = request.method().to_string();
-    let uri = request.uri().to_string();
+    let path = request.uri().path().to_string();
+    let query = request.uri().query().map(String::from);
+    let protocol = format!("{:?}", request.version());
     let headers = request.headers().clone();
-    
+    let request_id = request
+        .extensions()
+        .get::<RequestId>()
+        .and_then(|id| id.header_value().to_str().ok())
+        .map(sanitize_request_id)
+        .unwrap_or_else(|| "-".to_string());
+
+    if config.is_excluded(&path) {
+        return WithRequestId {
+            request_id,
+            inner: Box::pin(next.run(request)),
+        }
+        .await;
+    }
+
     // Extract user agent and referer from headers
     let user_agent = extract_header_value(&headers, "user-agent");
     let referer = extract_header_value(&headers, "referer");
-    
-    // Process the request
-    let response = next.run(request).await;
-    
+
+    // Process the request, tagging any logs it writes with the request id
+    let response = WithRequestId {
+        request_id: request_id.clone(),
+        inner: Box::pin(next.run(request)),
+    }
+    .await;
+
     // Extract response information
-    let status = response.status().as_u16();
-    let duration = start.elapsed();
-    
-    // Log the request with all captured information
-    // Using a dedicated logger target for access logs
-    log::info!(
-        target: "access",
-        "{} {} {} {} \"{}\" \"{}\" {}ms",
-        addr.ip(),
+    let cache_status = response.extensions().get::<CacheStatus>().copied();
+    let record = AccessRecord {
+        timestamp,
+        request_id,
+        ip: client_ip.to_string(),
         method,
-        uri,
-        status,
+        path,
+        query,
+        protocol,
+        status: response.status().as_u16(),
+        bytes_sent: 0,
+        ttfb_us: 0,
+        latency_us: 0,
         user_agent,
         referer,
-        duration.as_millis()
-    );
-    
-    response
+        cache: cache_status.map(|status| if status.hit { "hit" } else { "miss" }),
+        precompressed: cache_status.is_some_and(|status| status.precompressed),
+        aborted: false,
+    };
+
+    // The line is logged once the body has been streamed, so it can report
+    // the bytes sent and the total time
+    let (parts, body) = response.into_parts();
+    let expected_len = parts
+        .headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok()?.parse().ok())
+        .or_else(|| body.size_hint().exact());
+    let body = LoggedBody {
+        inner: body,
+        start,
+        first_byte: None,
+        bytes: 0,
+        expected_len,
+        pending: Some((record, format)),
+    };
+    Response::from_parts(parts, Body::new(body))
+}
+
+/// Response body that counts the bytes streamed through it and writes the
+/// access log line when it ends or is dropped.
+struct LoggedBody {
+    inner: Body,
+    start: Instant,
+    first_byte: Option<Duration>,
+    bytes: u64,
+    /// Length the body was declared to have, if known up front.
+    expected_len: Option<u64>,
+    pending: Option<(AccessRecord, AccessLogFormat)>,
+}
+
+impl LoggedBody {
+    fn finish(&mut self, aborted: bool) {
+        if let Some((mut record, format)) = self.pending.take() {
+            let elapsed = self.start.elapsed();
+            record.bytes_sent = self.bytes;
+            record.ttfb_us = self.first_byte.unwrap_or(elapsed).as_micros() as u64;
+            record.latency_us = elapsed.as_micros() as u64;
+            record.aborted = aborted;
+
+            log::info!(target: ACCESS_TARGET, "{}", record.format(format));
+        }
+    }
+}
+
+impl HttpBody for LoggedBody {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let poll = Pin::new(&mut self.inner).poll_frame(cx);
+        match &poll {
+            Poll::Ready(Some(Ok(frame))) => {
+                if let Some(data) = frame.data_ref() {
+                    if self.first_byte.is_none() && !data.is_empty() {
+                        self.first_byte = Some(self.start.elapsed());
+                    }
+                    self.bytes += data.len() as u64;
+                }
+            }
+            Poll::Ready(None) => self.finish(false),
+            Poll::Ready(Some(Err(_))) => self.finish(true),
+            Poll::Pending => {}
+        }
+        poll
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl Drop for LoggedBody {
+    fn drop(&mut self) {
+        // A body polled to its end has already been logged. Hyper stops
+        // polling once it has written a declared length, and never polls
+        // bodies of HEAD responses, so those are complete too.
+        let head = self.pending.as_ref().is_some_and(|(record, _)| record.method == "HEAD");
+        let complete = head || self.expected_len == Some(self.bytes);
+        self.finish(!complete);
+    }
+}
+
+/// Sets the request id in the log MDC whenever the wrapped future is polled,
+/// so it stays attached to logs even as the task moves between threads.
+struct WithRequestId<F> {
+    request_id: String,
+    inner: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for WithRequestId<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        log_mdc::insert(REQUEST_ID_KEY, self.request_id.clone());
+        let poll = self.inner.as_mut().poll(cx);
+        log_mdc::remove(REQUEST_ID_KEY);
+        poll
+    }
 }
 
 /// Helper function to safely extract header values
@@ -56,4 +381,17 @@ fn extract_header_value(headers: &HeaderMap, header_name: &str) -> String {
         .and_then(|value| value.to_str().ok())
         .unwrap_or("-")
         .to_string()
-}
\ No newline at end of file
+}
+
+/// Client-supplied ids are kept for correlation, but limited to a safe
+/// alphabet and length so they can't break up log lines.
+fn sanitize_request_id(id: &str) -> String {
+    id.chars()
+        .take(MAX_REQUEST_ID_LEN)
+        .map(|c| if c.is_ascii_alphanumeric() || "-_.:".contains(c) { c } else { '_' })
+        .collect()
+}
+
+fn escape_quotes(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
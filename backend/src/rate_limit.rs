@@ -0,0 +1,200 @@
+// backend/src/rate_limit.rs
+use crate::client_ip::ClientIp;
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use log::warn;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Buckets are swept of idle clients once the table grows past this size.
+const SWEEP_THRESHOLD: usize = 10_000;
+
+/// A token bucket: up to `burst` requests at once, refilled at
+/// `per_second` requests per second.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub burst: u32,
+    pub per_second: f64,
+}
+
+impl RateLimit {
+    pub fn new(burst: u32, per_second: f64) -> Self {
+        RateLimit { burst, per_second }
+    }
+
+    /// Parses `burst/per_second`, e.g. `20/10`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (burst, per_second) = value.split_once('/')?;
+        let burst = burst.trim().parse().ok().filter(|&burst| burst > 0)?;
+        let per_second = per_second.trim().parse().ok().filter(|&rate: &f64| rate > 0.0 && rate.is_finite())?;
+        Some(RateLimit::new(burst, per_second))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    /// Refills for the time elapsed since the last update.
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.per_second).min(limit.burst as f64);
+        self.updated = now;
+    }
+}
+
+/// Per-client token-bucket limits for individual routes or groups of routes.
+///
+/// Clients are identified by their [`ClientIp`], so requests forwarded by a
+/// trusted proxy count against the client rather than the proxy.
+#[derive(Debug)]
+pub struct RateLimiter {
+    limits: Vec<RateLimit>,
+    /// Path to its index in `limits`; paths in a group share an index.
+    routes: HashMap<String, usize>,
+    buckets: Mutex<HashMap<(usize, IpAddr), Bucket>>,
+}
+
+impl Default for RateLimiter {
+    /// Limits every endpoint that changes the board, and the image
+    /// renderers. Endpoints that can stand in for each other share a bucket,
+    /// so switching between them doesn't reset the limit.
+    fn default() -> Self {
+        RateLimiter::new()
+            .group(
+                &[
+                    "/api/game-of-life/step",
+                    "/api/game-of-life/back",
+                    "/api/game-of-life/seek",
+                    "/api/game-of-life/undo",
+                    "/api/game-of-life/redo",
+                ],
+                RateLimit::new(20, 10.0),
+            )
+            .group(&["/api/game-of-life/toggle", "/api/game-of-life/batch"], RateLimit::new(40, 20.0))
+            .route("/api/game-of-life/reset", RateLimit::new(5, 0.2))
+            .group(&["/api/game-of-life/image.png", "/api/game-of-life/image.svg"], RateLimit::new(10, 2.0))
+            .route("/api/game-of-life/animation.gif", RateLimit::new(3, 0.2))
+    }
+}
+
+impl RateLimiter {
+    /// A limiter with no limited routes.
+    pub fn new() -> Self {
+        RateLimiter {
+            limits: Vec::new(),
+            routes: HashMap::new(),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The default limits, adjusted by `RATE_LIMITS` (see
+    /// [`RateLimiter::with_overrides`]).
+    pub fn from_env() -> Self {
+        match std::env::var("RATE_LIMITS") {
+            Ok(overrides) => RateLimiter::default().with_overrides(&overrides),
+            Err(_) => RateLimiter::default(),
+        }
+    }
+
+    /// Applies comma-separated `path=burst/per_second` entries. A path that
+    /// is already limited changes the limit of its whole group; any other
+    /// path is limited on its own.
+    pub fn with_overrides(mut self, overrides: &str) -> Self {
+        for entry in overrides.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let parsed = entry
+                .split_once('=')
+                .and_then(|(path, limit)| Some((path.trim(), RateLimit::parse(limit)?)));
+            self = match parsed {
+                Some((path, limit)) => match self.routes.get(path) {
+                    Some(&index) => {
+                        self.limits[index] = limit;
+                        self
+                    }
+                    None => self.route(path, limit),
+                },
+                None => {
+                    warn!("Ignoring invalid rate limit: {}", entry);
+                    self
+                }
+            };
+        }
+        self
+    }
+
+    /// Limits requests to `path`, replacing any existing limit for it.
+    pub fn route(self, path: &str, limit: RateLimit) -> Self {
+        self.group(&[path], limit)
+    }
+
+    /// Limits requests to all of `paths` together: a client's requests to
+    /// any of them draw from one bucket.
+    pub fn group(mut self, paths: &[&str], limit: RateLimit) -> Self {
+        self.limits.push(limit);
+        for path in paths {
+            self.routes.insert(path.to_string(), self.limits.len() - 1);
+        }
+        self
+    }
+
+    /// Takes a token for `client` on `path`. Returns the seconds to wait
+    /// before retrying if the bucket is empty.
+    fn acquire(&self, path: &str, client: IpAddr) -> Result<(), u64> {
+        let Some(&index) = self.routes.get(path) else {
+            return Ok(());
+        };
+        let limit = &self.limits[index];
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() > SWEEP_THRESHOLD {
+            buckets.retain(|(index, _), bucket| {
+                let limit = &self.limits[*index];
+                bucket.refill(limit, now);
+                bucket.tokens < limit.burst as f64
+            });
+        }
+
+        let bucket = buckets.entry((index, client)).or_insert(Bucket {
+            tokens: limit.burst as f64,
+            updated: now,
+        });
+        bucket.refill(limit, now);
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(((1.0 - bucket.tokens) / limit.per_second).ceil().max(1.0) as u64)
+        }
+    }
+}
+
+/// Rejects requests over their route's limit with `429 Too Many Requests`
+/// and a `Retry-After` header. Requests without a client address, such as
+/// those from in-process tests, are not limited.
+pub async fn rate_limit(State(limiter): State<Arc<RateLimiter>>, request: Request, next: Next) -> Response {
+    let Some(client) = ClientIp::from_request(&request) else {
+        return next.run(request).await;
+    };
+    let path = request.uri().path();
+
+    match limiter.acquire(path, client) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            warn!("Rate limited {} {} {}, retry after {}s", client, request.method(), path, retry_after);
+            let mut response = (StatusCode::TOO_MANY_REQUESTS, "Too many requests").into_response();
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+            response
+        }
+    }
+}
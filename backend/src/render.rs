@@ -0,0 +1,226 @@
+// backend/src/render.rs
+use serde::Deserialize;
+use std::fmt::Write;
+
+/// Largest cell size accepted for rendered images, in pixels.
+pub const MAX_CELL_SIZE: usize = 64;
+/// Most generations a single animation may contain.
+pub const MAX_ANIMATION_GENERATIONS: usize = 500;
+/// Most pixels a single animation frame may contain.
+pub const MAX_ANIMATION_FRAME_PIXELS: usize = 800 * 800;
+
+/// Palette index of dead cells in a rasterized board.
+pub const DEAD: u8 = 0;
+/// Palette index of live cells in a rasterized board.
+pub const ALIVE: u8 = 1;
+/// Palette index of grid lines in a rasterized board.
+pub const GRID: u8 = 2;
+
+/// An RGB color, written and parsed as a six-digit hex string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color(pub u8, pub u8, pub u8);
+
+impl Color {
+    /// Parses `rrggbb`, with or without a leading `#`.
+    pub fn parse(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        Some(Color(channel(0)?, channel(2)?, channel(4)?))
+    }
+
+    pub fn hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+/// Query parameters controlling how a board is drawn.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RenderQuery {
+    pub cell: usize,
+    pub alive: String,
+    pub dead: String,
+    pub grid: bool,
+    pub grid_color: String,
+}
+
+impl Default for RenderQuery {
+    fn default() -> Self {
+        RenderQuery {
+            cell: 10,
+            alive: "ffffff".to_string(),
+            dead: "000000".to_string(),
+            grid: false,
+            grid_color: "333333".to_string(),
+        }
+    }
+}
+
+/// Validated drawing options.
+#[derive(Debug, Clone, Copy)]
+pub struct RenderOptions {
+    pub cell_size: usize,
+    pub alive: Color,
+    pub dead: Color,
+    pub grid_lines: Option<Color>,
+}
+
+impl RenderOptions {
+    pub fn from_query(query: &RenderQuery) -> Result<Self, String> {
+        if query.cell == 0 || query.cell > MAX_CELL_SIZE {
+            return Err(format!("Cell size must be between 1 and {}", MAX_CELL_SIZE));
+        }
+        if query.grid && query.cell < 2 {
+            return Err("Grid lines need a cell size of at least 2".to_string());
+        }
+        let color = |name: &str, value: &str| {
+            Color::parse(value).ok_or_else(|| format!("Invalid {} color: {}", name, value))
+        };
+        Ok(RenderOptions {
+            cell_size: query.cell,
+            alive: color("alive", &query.alive)?,
+            dead: color("dead", &query.dead)?,
+            grid_lines: if query.grid {
+                Some(color("grid", &query.grid_color)?)
+            } else {
+                None
+            },
+        })
+    }
+
+    /// Palette indexed by [`DEAD`], [`ALIVE`] and, with grid lines, [`GRID`].
+    pub fn palette(&self) -> Vec<Color> {
+        let mut palette = vec![self.dead, self.alive];
+        palette.extend(self.grid_lines);
+        palette
+    }
+
+    /// Pixel dimensions of a rendered `width`x`height` board. Grid lines add
+    /// a one pixel border on the right and bottom.
+    pub fn image_size(&self, width: usize, height: usize) -> (usize, usize) {
+        let border = self.grid_lines.is_some() as usize;
+        (width * self.cell_size + border, height * self.cell_size + border)
+    }
+}
+
+/// Rasterizes a board into one palette index per pixel, row-major.
+pub fn rasterize(grid: &[u8], width: usize, height: usize, options: &RenderOptions) -> Vec<u8> {
+    let (image_width, image_height) = options.image_size(width, height);
+    let cell = options.cell_size;
+    let grid_lines = options.grid_lines.is_some();
+    let mut pixels = vec![DEAD; image_width * image_height];
+    for (y, row) in pixels.chunks_mut(image_width).enumerate() {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            *pixel = if grid_lines && (x % cell == 0 || y % cell == 0) {
+                GRID
+            } else {
+                let (row, col) = (y / cell, x / cell);
+                if row < height && col < width && grid[row * width + col] != 0 {
+                    ALIVE
+                } else {
+                    DEAD
+                }
+            };
+        }
+    }
+    pixels
+}
+
+/// Encodes a board as an indexed-color PNG.
+pub fn render_png(grid: &[u8], width: usize, height: usize, options: &RenderOptions) -> Result<Vec<u8>, png::EncodingError> {
+    let (image_width, image_height) = options.image_size(width, height);
+    let pixels = rasterize(grid, width, height, options);
+    let palette: Vec<u8> = options.palette().iter().flat_map(|c| [c.0, c.1, c.2]).collect();
+
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, image_width as u32, image_height as u32);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_palette(palette);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&pixels)?;
+    writer.finish()?;
+    Ok(out)
+}
+
+/// Renders a board as an SVG document with one rect per live cell.
+pub fn render_svg(grid: &[u8], width: usize, height: usize, options: &RenderOptions) -> String {
+    let (image_width, image_height) = options.image_size(width, height);
+    let cell = options.cell_size;
+    let offset = options.grid_lines.is_some() as usize;
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" shape-rendering="crispEdges">"#,
+        w = image_width,
+        h = image_height
+    );
+    let _ = write!(svg, r#"<rect width="100%" height="100%" fill="{}"/>"#, options.dead.hex());
+
+    let _ = write!(svg, r#"<g fill="{}">"#, options.alive.hex());
+    for (idx, _) in grid.iter().enumerate().filter(|(_, cell)| **cell != 0) {
+        let (row, col) = (idx / width, idx % width);
+        let _ = write!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{}" height="{}"/>"#,
+            col * cell + offset,
+            row * cell + offset,
+            cell - offset,
+            cell - offset
+        );
+    }
+    svg.push_str("</g>");
+
+    if let Some(grid_color) = options.grid_lines {
+        let mut path = String::new();
+        for col in 0..=width {
+            let _ = write!(path, "M{} 0V{}", col * cell, image_height);
+        }
+        for row in 0..=height {
+            let _ = write!(path, "M0 {}H{}", row * cell, image_width);
+        }
+        let _ = write!(
+            svg,
+            r#"<path d="{}" stroke="{}" stroke-width="1" transform="translate(0.5 0.5)"/>"#,
+            path,
+            grid_color.hex()
+        );
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Encodes successive boards as a looping GIF using the options' palette,
+/// so each frame is just one palette index per pixel.
+pub fn render_gif<I>(
+    frames: I,
+    width: usize,
+    height: usize,
+    options: &RenderOptions,
+    delay_ms: u16,
+) -> Result<Vec<u8>, gif::EncodingError>
+where
+    I: IntoIterator<Item = Vec<u8>>,
+{
+    let (image_width, image_height) = options.image_size(width, height);
+    let palette: Vec<u8> = options.palette().iter().flat_map(|c| [c.0, c.1, c.2]).collect();
+    let delay = delay_ms.div_ceil(10);
+
+    let mut out = Vec::new();
+    {
+        let mut encoder = gif::Encoder::new(&mut out, image_width as u16, image_height as u16, &palette)?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+        for grid in frames {
+            let pixels = rasterize(&grid, width, height, options);
+            let mut frame = gif::Frame::from_indexed_pixels(image_width as u16, image_height as u16, pixels, None);
+            frame.delay = delay;
+            encoder.write_frame(&frame)?;
+        }
+    }
+    Ok(out)
+}
@@ -1,7 +1,13 @@
 // backend/src/routes.rs
-use crate::game_of_life::{GameOfLife, HEIGHT, WIDTH};
+use crate::encoding::StateFormat;
+use crate::render::{
+    render_gif, render_png, render_svg, RenderOptions, RenderQuery, MAX_ANIMATION_FRAME_PIXELS,
+    MAX_ANIMATION_GENERATIONS,
+};
+use crate::sitemap::Sitemap;
+use crate::game_of_life::{density_blocks, region, update, GameOfLife, HEIGHT, WIDTH};
 use axum::{
-    extract::{Extension, Query},
+    extract::{Extension, Path, Query},
     response::{Json, IntoResponse, Response},
     routing::{get, post},
     Router,
@@ -16,6 +22,70 @@ pub struct GameState {
     pub grid: Vec<u8>,
     pub width: usize,
     pub height: usize,
+    pub generation: usize,
+    /// Column of the grid's left edge on the full board.
+    pub x: usize,
+    /// Row of the grid's top edge on the full board.
+    pub y: usize,
+}
+
+impl GameState {
+    pub fn from_game(gol: &GameOfLife) -> Self {
+        GameState {
+            grid: gol.current.clone(),
+            width: WIDTH,
+            height: HEIGHT,
+            generation: gol.generation,
+            x: 0,
+            y: 0,
+        }
+    }
+}
+
+/// Optional viewport for `GET /state`; omitted bounds default to the rest of
+/// the board.
+#[derive(Debug, Default, Deserialize)]
+pub struct ViewportQuery {
+    pub x: Option<usize>,
+    pub y: Option<usize>,
+    pub w: Option<usize>,
+    pub h: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct AnimationQuery {
+    pub generations: usize,
+    pub delay: u16,
+}
+
+impl Default for AnimationQuery {
+    fn default() -> Self {
+        AnimationQuery { generations: 50, delay: 100 }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OverviewQuery {
+    pub block: usize,
+}
+
+/// Downsampled board for minimaps: the live-cell fraction of each block.
+#[derive(Debug, Serialize)]
+pub struct Overview {
+    pub density: Vec<f32>,
+    pub block: usize,
+    pub width: usize,
+    pub height: usize,
+    pub generation: usize,
+}
+
+/// Range of generations available for scrubbing.
+#[derive(Debug, Serialize)]
+pub struct TimelineInfo {
+    pub origin: usize,
+    pub latest: usize,
+    pub generation: usize,
 }
 
 #[derive(Debug, Deserialize)]
@@ -24,117 +94,314 @@ pub struct ToggleQuery {
     pub col: usize,
 }
 
-pub async fn get_state(Extension(state): Extension<Arc<Mutex<GameOfLife>>>) -> Json<GameState> {
+#[derive(Debug, Deserialize)]
+pub struct SeekQuery {
+    #[serde(rename = "gen")]
+    pub generation: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CellEdit {
+    pub row: usize,
+    pub col: usize,
+    pub alive: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BatchEdit {
+    pub cells: Vec<CellEdit>,
+}
+
+pub async fn get_state(
+    format: StateFormat,
+    Query(viewport): Query<ViewportQuery>,
+    Extension(state): Extension<Arc<Mutex<GameOfLife>>>,
+) -> Result<Response, (StatusCode, String)> {
     info!("Received GET state request");
+    let x = viewport.x.unwrap_or(0);
+    let y = viewport.y.unwrap_or(0);
+    if x >= WIDTH || y >= HEIGHT {
+        return Err((StatusCode::BAD_REQUEST, format!("Viewport origin ({}, {}) is off the board", x, y)));
+    }
+    let w = viewport.w.unwrap_or(WIDTH - x);
+    let h = viewport.h.unwrap_or(HEIGHT - y);
+    if w == 0 || h == 0 || w > WIDTH.saturating_sub(x) || h > HEIGHT.saturating_sub(y) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Viewport {}x{} at ({}, {}) does not fit the {}x{} board", w, h, x, y, WIDTH, HEIGHT),
+        ));
+    }
+
     let gol = state.lock().unwrap();
     let response = GameState {
-        grid: gol.current.clone(),
-        width: WIDTH,
-        height: HEIGHT,
+        grid: region(&gol.current, x, y, w, h),
+        width: w,
+        height: h,
+        generation: gol.generation,
+        x,
+        y,
     };
     info!("Responding with updated state");
-    Json(response)
+    Ok(format.respond(response))
 }
 
-pub async fn step(Extension(state): Extension<Arc<Mutex<GameOfLife>>>) -> Json<GameState> {
+pub async fn overview(
+    Query(query): Query<OverviewQuery>,
+    Extension(state): Extension<Arc<Mutex<GameOfLife>>>,
+) -> Result<Json<Overview>, (StatusCode, String)> {
+    info!("Received GET overview request with block size {}", query.block);
+    if query.block == 0 {
+        return Err((StatusCode::BAD_REQUEST, "Block size must be positive".to_string()));
+    }
+    let gol = state.lock().unwrap();
+    let (density, width, height) = density_blocks(&gol.current, query.block);
+    Ok(Json(Overview {
+        density,
+        block: query.block,
+        width,
+        height,
+        generation: gol.generation,
+    }))
+}
+
+pub async fn step(
+    format: StateFormat,
+    Extension(state): Extension<Arc<Mutex<GameOfLife>>>,
+) -> Response {
     info!("Received POST step request");
     let mut gol = state.lock().unwrap();
     gol.step();
-    let response = GameState {
-        grid: gol.current.clone(),
-        width: WIDTH,
-        height: HEIGHT,
-    };
+    let response = GameState::from_game(&gol);
     info!("Responding with updated state");
-    Json(response)
+    format.respond(response)
 }
 
-pub async fn step_back(Extension(state): Extension<Arc<Mutex<GameOfLife>>>) -> Json<GameState> {
+pub async fn step_back(
+    format: StateFormat,
+    Extension(state): Extension<Arc<Mutex<GameOfLife>>>,
+) -> Response {
     info!("Received POST step_back request");
     let mut gol = state.lock().unwrap();
     gol.step_back();
-    let response = GameState {
-        grid: gol.current.clone(),
-        width: WIDTH,
-        height: HEIGHT,
-    };
+    let response = GameState::from_game(&gol);
     info!("Responding with updated state");
-    Json(response)
+    format.respond(response)
 }
 
 pub async fn toggle_cell(
+    format: StateFormat,
     Query(query): Query<ToggleQuery>,
     Extension(state): Extension<Arc<Mutex<GameOfLife>>>,
-) -> Json<GameState> {
+) -> Response {
     info!("Received POST toggle request for row: {}, col: {}", query.row, query.col);
     let mut gol = state.lock().unwrap();
     gol.toggle_cell(query.row, query.col);
-    let response = GameState {
-        grid: gol.current.clone(),
-        width: WIDTH,
-        height: HEIGHT,
-    };
+    let response = GameState::from_game(&gol);
+    info!("Responding with updated state");
+    format.respond(response)
+}
+
+pub async fn batch_edit(
+    format: StateFormat,
+    Extension(state): Extension<Arc<Mutex<GameOfLife>>>,
+    Json(edit): Json<BatchEdit>,
+) -> Response {
+    info!("Received POST batch edit request for {} cells", edit.cells.len());
+    let cells: Vec<_> = edit.cells.iter().map(|c| (c.row, c.col, c.alive)).collect();
+    let mut gol = state.lock().unwrap();
+    gol.set_cells(&cells);
+    let response = GameState::from_game(&gol);
+    info!("Responding with updated state");
+    format.respond(response)
+}
+
+pub async fn undo(
+    format: StateFormat,
+    Extension(state): Extension<Arc<Mutex<GameOfLife>>>,
+) -> Response {
+    info!("Received POST undo request");
+    let mut gol = state.lock().unwrap();
+    gol.undo();
+    let response = GameState::from_game(&gol);
     info!("Responding with updated state");
-    Json(response)
+    format.respond(response)
 }
 
-pub async fn reset(Extension(state): Extension<Arc<Mutex<GameOfLife>>>) -> Json<GameState> {
+pub async fn redo(
+    format: StateFormat,
+    Extension(state): Extension<Arc<Mutex<GameOfLife>>>,
+) -> Response {
+    info!("Received POST redo request");
+    let mut gol = state.lock().unwrap();
+    gol.redo();
+    let response = GameState::from_game(&gol);
+    info!("Responding with updated state");
+    format.respond(response)
+}
+
+pub async fn reset(
+    format: StateFormat,
+    Extension(state): Extension<Arc<Mutex<GameOfLife>>>,
+) -> Response {
     info!("Received POST reset request");
     let mut gol = state.lock().unwrap();
     gol.reset();
-    let response = GameState {
-        grid: gol.current.clone(),
+    let response = GameState::from_game(&gol);
+    info!("Responding with updated state");
+    format.respond(response)
+}
+
+pub async fn get_generation(
+    format: StateFormat,
+    Path(generation): Path<usize>,
+    Extension(state): Extension<Arc<Mutex<GameOfLife>>>,
+) -> Result<Response, (StatusCode, String)> {
+    info!("Received GET generation request for generation {}", generation);
+    let gol = state.lock().unwrap();
+    let grid = gol.generation_board(generation).ok_or_else(|| {
+        (StatusCode::NOT_FOUND, format!("Generation {} is not in the retained history", generation))
+    })?;
+    info!("Responding with historical state");
+    Ok(format.respond(GameState {
+        grid,
         width: WIDTH,
         height: HEIGHT,
-    };
+        generation,
+        x: 0,
+        y: 0,
+    }))
+}
+
+pub async fn seek(
+    format: StateFormat,
+    Query(query): Query<SeekQuery>,
+    Extension(state): Extension<Arc<Mutex<GameOfLife>>>,
+) -> Result<Response, (StatusCode, String)> {
+    info!("Received POST seek request for generation {}", query.generation);
+    let mut gol = state.lock().unwrap();
+    if !gol.seek(query.generation) {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("Generation {} is not in the retained history", query.generation),
+        ));
+    }
+    let response = GameState::from_game(&gol);
     info!("Responding with updated state");
-    Json(response)
+    Ok(format.respond(response))
+}
+
+pub async fn timeline(Extension(state): Extension<Arc<Mutex<GameOfLife>>>) -> Json<TimelineInfo> {
+    info!("Received GET timeline request");
+    let gol = state.lock().unwrap();
+    Json(TimelineInfo {
+        origin: gol.timeline.origin(),
+        latest: gol.timeline.latest(),
+        generation: gol.generation,
+    })
+}
+
+/// Renders the current board as a PNG image.
+pub async fn image_png(
+    Query(query): Query<RenderQuery>,
+    Extension(state): Extension<Arc<Mutex<GameOfLife>>>,
+) -> Result<Response, (StatusCode, String)> {
+    info!("Received GET image.png request");
+    let options = RenderOptions::from_query(&query).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let grid = state.lock().unwrap().current.clone();
+    let png = render_png(&grid, WIDTH, HEIGHT, &options)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to encode PNG: {}", e)))?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(header::CONTENT_TYPE, "image/png".parse().unwrap());
+    headers.insert(header::CACHE_CONTROL, "no-cache".parse().unwrap());
+    Ok((StatusCode::OK, headers, png).into_response())
+}
+
+/// Renders the current board as an SVG image.
+pub async fn image_svg(
+    Query(query): Query<RenderQuery>,
+    Extension(state): Extension<Arc<Mutex<GameOfLife>>>,
+) -> Result<Response, (StatusCode, String)> {
+    info!("Received GET image.svg request");
+    let options = RenderOptions::from_query(&query).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let grid = state.lock().unwrap().current.clone();
+    let svg = render_svg(&grid, WIDTH, HEIGHT, &options);
+
+    let mut headers = HeaderMap::new();
+    headers.insert(header::CONTENT_TYPE, "image/svg+xml".parse().unwrap());
+    headers.insert(header::CACHE_CONTROL, "no-cache".parse().unwrap());
+    Ok((StatusCode::OK, headers, svg).into_response())
+}
+
+/// Renders the next `generations` boards from the current state as a looping
+/// GIF, without advancing the shared game.
+pub async fn animation_gif(
+    Query(animation): Query<AnimationQuery>,
+    Query(query): Query<RenderQuery>,
+    Extension(state): Extension<Arc<Mutex<GameOfLife>>>,
+) -> Result<Response, (StatusCode, String)> {
+    info!("Received GET animation.gif request for {} generations", animation.generations);
+    if animation.generations == 0 || animation.generations > MAX_ANIMATION_GENERATIONS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Generations must be between 1 and {}", MAX_ANIMATION_GENERATIONS),
+        ));
+    }
+    let options = RenderOptions::from_query(&query).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let (image_width, image_height) = options.image_size(WIDTH, HEIGHT);
+    if image_width * image_height > MAX_ANIMATION_FRAME_PIXELS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Frames of {}x{} pixels exceed the animation size limit", image_width, image_height),
+        ));
+    }
+
+    let start = state.lock().unwrap().current.clone();
+    let gif = tokio::task::spawn_blocking(move || {
+        let frames = std::iter::successors(Some(start), |grid| {
+            let mut next = vec![0u8; WIDTH * HEIGHT];
+            update(grid, &mut next);
+            Some(next)
+        })
+        .take(animation.generations);
+        render_gif(frames, WIDTH, HEIGHT, &options, animation.delay)
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Animation task failed: {}", e)))?
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to encode GIF: {}", e)))?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(header::CONTENT_TYPE, "image/gif".parse().unwrap());
+    headers.insert(header::CACHE_CONTROL, "no-cache".parse().unwrap());
+    Ok((StatusCode::OK, headers, gif).into_response())
 }
 
 /// Generates XML sitemap for the portfolio website
-pub async fn sitemap() -> Response {
+pub async fn sitemap(Extension(sitemap): Extension<Sitemap>) -> Response {
     info!("Received sitemap request");
-    
-    let sitemap_xml = r#"<?xml version="1.0" encoding="UTF-8"?>
-<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
-    <url>
-        <loc>https://ryanrumana.com/</loc>
-        <lastmod>2025-01-01</lastmod>
-        <changefreq>monthly</changefreq>
-        <priority>1.0</priority>
-    </url>
-    <url>
-        <loc>https://ryanrumana.com/about.html</loc>
-        <lastmod>2025-01-01</lastmod>
-        <changefreq>monthly</changefreq>
-        <priority>0.8</priority>
-    </url>
-    <url>
-        <loc>https://ryanrumana.com/game_of_life.html</loc>
-        <lastmod>2025-01-01</lastmod>
-        <changefreq>monthly</changefreq>
-        <priority>0.7</priority>
-    </url>
-    <url>
-        <loc>https://ryanrumana.com/ReID.html</loc>
-        <lastmod>2025-01-01</lastmod>
-        <changefreq>monthly</changefreq>
-        <priority>0.7</priority>
-    </url>
-</urlset>"#;
 
     let mut headers = HeaderMap::new();
     headers.insert(header::CONTENT_TYPE, "application/xml".parse().unwrap());
     
-    (StatusCode::OK, headers, sitemap_xml).into_response()
+    (StatusCode::OK, headers, sitemap.xml()).into_response()
 }
 
 /// Assembles the Game of Life API router.
 pub fn game_api() -> Router {
     Router::new()
         .route("/state", get(get_state))
+        .route("/overview", get(overview))
         .route("/step", post(step))
         .route("/back", post(step_back))
         .route("/toggle", post(toggle_cell))
+        .route("/batch", post(batch_edit))
         .route("/reset", post(reset))
+        .route("/undo", post(undo))
+        .route("/redo", post(redo))
+        .route("/timeline", get(timeline))
+        .route("/generation/{n}", get(get_generation))
+        .route("/seek", post(seek))
+        .route("/image.png", get(image_png))
+        .route("/image.svg", get(image_svg))
+        .route("/animation.gif", get(animation_gif))
 }
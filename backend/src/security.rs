@@ -0,0 +1,139 @@
+// backend/src/security.rs
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+
+/// Security headers applied to one group of routes. `None` leaves a header
+/// unset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecurityPolicy {
+    pub content_security_policy: Option<String>,
+    /// Appended to the CSP as its `frame-ancestors` directive.
+    pub frame_ancestors: Option<String>,
+    pub strict_transport_security: Option<String>,
+    /// Sends `X-Content-Type-Options: nosniff`.
+    pub nosniff: bool,
+    pub referrer_policy: Option<String>,
+    pub permissions_policy: Option<String>,
+}
+
+impl SecurityPolicy {
+    /// Policy for the HTML pages: same-origin resources plus the CDNs the
+    /// pages link to. Inline scripts and styles are used throughout the
+    /// pages, and `wasm-unsafe-eval` lets the Game of Life compile its WASM
+    /// module.
+    pub fn site() -> Self {
+        SecurityPolicy {
+            content_security_policy: Some(
+                [
+                    "default-src 'self'",
+                    "script-src 'self' 'unsafe-inline' 'wasm-unsafe-eval' https://cdnjs.cloudflare.com",
+                    "style-src 'self' 'unsafe-inline' https://cdnjs.cloudflare.com https://fonts.googleapis.com",
+                    "font-src 'self' data: https://cdnjs.cloudflare.com https://fonts.gstatic.com",
+                    "img-src 'self' data:",
+                    "connect-src 'self'",
+                    "object-src 'none'",
+                    "base-uri 'self'",
+                    "form-action 'self'",
+                ]
+                .join("; "),
+            ),
+            frame_ancestors: Some("'none'".to_string()),
+            strict_transport_security: Some("max-age=63072000; includeSubDomains".to_string()),
+            nosniff: true,
+            referrer_policy: Some("strict-origin-when-cross-origin".to_string()),
+            permissions_policy: Some(
+                "camera=(), microphone=(), geolocation=(), payment=(), usb=(), interest-cohort=()".to_string(),
+            ),
+        }
+    }
+
+    /// Policy for JSON and image API responses, which never load anything.
+    pub fn api() -> Self {
+        SecurityPolicy {
+            content_security_policy: Some("default-src 'none'".to_string()),
+            referrer_policy: Some("no-referrer".to_string()),
+            ..SecurityPolicy::site()
+        }
+    }
+
+    fn headers(&self) -> Vec<(HeaderName, HeaderValue)> {
+        let csp = match (&self.content_security_policy, &self.frame_ancestors) {
+            (Some(csp), Some(ancestors)) => Some(format!("{}; frame-ancestors {}", csp, ancestors)),
+            (Some(csp), None) => Some(csp.clone()),
+            (None, Some(ancestors)) => Some(format!("frame-ancestors {}", ancestors)),
+            (None, None) => None,
+        };
+        let nosniff = self.nosniff.then(|| "nosniff".to_string());
+        [
+            (header::CONTENT_SECURITY_POLICY, csp),
+            (header::STRICT_TRANSPORT_SECURITY, self.strict_transport_security.clone()),
+            (header::X_CONTENT_TYPE_OPTIONS, nosniff),
+            (header::REFERRER_POLICY, self.referrer_policy.clone()),
+            (HeaderName::from_static("permissions-policy"), self.permissions_policy.clone()),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, HeaderValue::from_str(&value?).ok()?)))
+        .collect()
+    }
+}
+
+/// Security policies by route prefix. The longest matching prefix wins, and
+/// paths matching none get the default.
+#[derive(Debug, Clone)]
+pub struct SecurityHeaders {
+    default: Vec<(HeaderName, HeaderValue)>,
+    routes: Vec<(String, Vec<(HeaderName, HeaderValue)>)>,
+}
+
+impl Default for SecurityHeaders {
+    fn default() -> Self {
+        SecurityHeaders::new(SecurityPolicy::site()).route("/api/", SecurityPolicy::api())
+    }
+}
+
+impl SecurityHeaders {
+    pub fn new(default: SecurityPolicy) -> Self {
+        SecurityHeaders {
+            default: default.headers(),
+            routes: Vec::new(),
+        }
+    }
+
+    /// Uses `policy` for paths starting with `prefix`.
+    pub fn route(mut self, prefix: &str, policy: SecurityPolicy) -> Self {
+        self.routes.retain(|(existing, _)| existing != prefix);
+        self.routes.push((prefix.to_string(), policy.headers()));
+        self.routes.sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+        self
+    }
+
+    fn headers_for(&self, path: &str) -> &[(HeaderName, HeaderValue)] {
+        self.routes
+            .iter()
+            .find(|(prefix, _)| path.starts_with(prefix.as_str()))
+            .map_or(&self.default, |(_, headers)| headers)
+    }
+}
+
+/// Adds the security headers for the request path to its response, leaving
+/// any a handler already set.
+pub async fn security_headers(
+    State(config): State<Arc<SecurityHeaders>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_string();
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    for (name, value) in config.headers_for(&path) {
+        if !headers.contains_key(name) {
+            headers.insert(name.clone(), value.clone());
+        }
+    }
+    response
+}
@@ -0,0 +1,271 @@
+// backend/src/sitemap.rs
+use log::{info, warn};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Base URL used when `SITE_URL` is not set.
+pub const DEFAULT_BASE_URL: &str = "https://ryanrumana.com";
+/// How often the static directory is rescanned for changes.
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Priority and change frequency for a page that differ from the defaults.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PageOverride {
+    pub priority: Option<f32>,
+    pub changefreq: Option<String>,
+}
+
+/// Which pages go into the sitemap and how they are described.
+///
+/// Pages are keyed by their path relative to the static root, using `/`
+/// separators, e.g. `index.html` or `projects/life.html`.
+#[derive(Debug, Clone)]
+pub struct SitemapConfig {
+    pub base_url: String,
+    pub default_priority: f32,
+    pub default_changefreq: String,
+    pub overrides: HashMap<String, PageOverride>,
+    /// Pages to leave out. Entries ending in `/` exclude a whole directory.
+    pub exclude: Vec<String>,
+}
+
+impl Default for SitemapConfig {
+    fn default() -> Self {
+        let overrides = [("index.html", 1.0), ("about.html", 0.8)]
+            .into_iter()
+            .map(|(page, priority)| {
+                let page_override = PageOverride {
+                    priority: Some(priority),
+                    changefreq: None,
+                };
+                (page.to_string(), page_override)
+            })
+            .collect();
+        SitemapConfig {
+            base_url: DEFAULT_BASE_URL.to_string(),
+            default_priority: 0.7,
+            default_changefreq: "monthly".to_string(),
+            overrides,
+            exclude: ["error.html", "404.html", "500.html", "503.html"].map(String::from).to_vec(),
+        }
+    }
+}
+
+impl SitemapConfig {
+    /// Defaults adjusted by the environment: `SITE_URL` sets the base URL and
+    /// `SITEMAP_EXCLUDE` adds comma-separated pages to the exclude list.
+    pub fn from_env() -> Self {
+        let mut config = SitemapConfig::default();
+        if let Ok(base_url) = std::env::var("SITE_URL") {
+            config.base_url = base_url;
+        }
+        if let Ok(exclude) = std::env::var("SITEMAP_EXCLUDE") {
+            config.exclude.extend(
+                exclude
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|page| !page.is_empty())
+                    .map(String::from),
+            );
+        }
+        config
+    }
+
+    fn is_excluded(&self, page: &str) -> bool {
+        self.exclude.iter().any(|entry| {
+            if entry.ends_with('/') {
+                page.starts_with(entry.as_str())
+            } else {
+                page == entry
+            }
+        })
+    }
+}
+
+/// An HTML page found under the static root.
+#[derive(Debug, Clone, PartialEq)]
+struct Page {
+    path: String,
+    modified: SystemTime,
+}
+
+#[derive(Debug, Default)]
+struct Cached {
+    pages: Vec<Page>,
+    xml: String,
+}
+
+/// The sitemap for a static directory, rebuilt whenever its pages change.
+///
+/// Cheap to clone; clones share the same cached document.
+#[derive(Debug, Clone)]
+pub struct Sitemap {
+    root: PathBuf,
+    config: Arc<SitemapConfig>,
+    cached: Arc<RwLock<Cached>>,
+}
+
+impl Sitemap {
+    /// Scans `root` and builds the initial sitemap.
+    pub fn new(root: impl Into<PathBuf>, config: SitemapConfig) -> Self {
+        let sitemap = Sitemap {
+            root: root.into(),
+            config: Arc::new(config),
+            cached: Arc::new(RwLock::new(Cached::default())),
+        };
+        sitemap.rebuild(sitemap.scan());
+        sitemap
+    }
+
+    /// The current sitemap document.
+    pub fn xml(&self) -> String {
+        self.cached.read().unwrap().xml.clone()
+    }
+
+    /// Rescans the static root, rebuilding the sitemap if any page was added,
+    /// removed or modified. Returns whether it changed.
+    pub fn refresh(&self) -> bool {
+        let pages = self.scan();
+        if self.cached.read().unwrap().pages == pages {
+            return false;
+        }
+        self.rebuild(pages);
+        true
+    }
+
+    /// Spawns a task that refreshes the sitemap every `interval`.
+    pub fn watch(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let sitemap = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let sitemap = sitemap.clone();
+                if let Err(e) = tokio::task::spawn_blocking(move || sitemap.refresh()).await {
+                    warn!("Sitemap refresh failed: {}", e);
+                }
+            }
+        })
+    }
+
+    fn rebuild(&self, pages: Vec<Page>) {
+        let xml = render(&pages, &self.config);
+        info!("Built sitemap with {} pages", pages.len());
+        *self.cached.write().unwrap() = Cached { pages, xml };
+    }
+
+    /// Lists the HTML pages under the root, sorted by path.
+    fn scan(&self) -> Vec<Page> {
+        let mut pages = Vec::new();
+        let mut dirs = vec![self.root.clone()];
+        while let Some(dir) = dirs.pop() {
+            let entries = match std::fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    warn!("Cannot scan {} for the sitemap: {}", dir.display(), e);
+                    continue;
+                }
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if entry.file_name().to_string_lossy().starts_with('.') {
+                    continue;
+                }
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                if metadata.is_dir() {
+                    dirs.push(path);
+                } else if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("html"))
+                    && let Some(page) = relative_page(&self.root, &path)
+                    && !self.config.is_excluded(&page)
+                {
+                    pages.push(Page {
+                        path: page,
+                        modified: metadata.modified().unwrap_or(UNIX_EPOCH),
+                    });
+                }
+            }
+        }
+        pages.sort_by(|a, b| a.path.cmp(&b.path));
+        pages
+    }
+}
+
+/// Path of `file` relative to `root` with `/` separators.
+fn relative_page(root: &Path, file: &Path) -> Option<String> {
+    let relative = file.strip_prefix(root).ok()?;
+    let parts: Option<Vec<&str>> = relative.components().map(|c| c.as_os_str().to_str()).collect();
+    Some(parts?.join("/"))
+}
+
+/// URL path a page is served at; `index.html` maps to its directory.
+fn page_url(page: &str) -> &str {
+    if page == "index.html" {
+        ""
+    } else {
+        page.strip_suffix("/index.html").map_or(page, |dir| &page[..dir.len() + 1])
+    }
+}
+
+fn render(pages: &[Page], config: &SitemapConfig) -> String {
+    let base_url = config.base_url.trim_end_matches('/');
+    let mut entries: Vec<(f32, &Page)> = pages
+        .iter()
+        .map(|page| {
+            let priority = config
+                .overrides
+                .get(&page.path)
+                .and_then(|o| o.priority)
+                .unwrap_or(config.default_priority);
+            (priority, page)
+        })
+        .collect();
+    entries.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.path.cmp(&b.1.path)));
+
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for (priority, page) in entries {
+        let changefreq = config
+            .overrides
+            .get(&page.path)
+            .and_then(|o| o.changefreq.as_deref())
+            .unwrap_or(&config.default_changefreq);
+        let _ = write!(
+            xml,
+            "    <url>\n        <loc>{}/{}</loc>\n        <lastmod>{}</lastmod>\n        <changefreq>{}</changefreq>\n        <priority>{:.1}</priority>\n    </url>\n",
+            escape_xml(base_url),
+            escape_xml(page_url(&page.path)),
+            iso_date(page.modified),
+            escape_xml(changefreq),
+            priority
+        );
+    }
+    xml.push_str("</urlset>");
+    xml
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// Formats a time as a `YYYY-MM-DD` UTC date.
+pub fn iso_date(time: SystemTime) -> String {
+    chrono::DateTime::<chrono::Utc>::from(time).format("%Y-%m-%d").to_string()
+}
@@ -3,14 +3,110 @@ use axum::{
     body::{Body, to_bytes},
     http::{Request, StatusCode},
 };
-use backend::game_of_life::{GameOfLife, HEIGHT, WIDTH};
+use backend::game_of_life::{
+    GameOfLife, parse_initial_state, CHECKPOINT_INTERVAL, HEIGHT, MAX_HISTORY, MAX_TIMELINE, RETAINED_SNAPSHOTS, WIDTH,
+};
+use backend::encoding::{encode_rle, unpack_bits};
+use backend::health::Health;
+use backend::metrics::Metrics;
+use backend::rate_limit::{RateLimit, RateLimiter};
 use backend::routes::{game_api};
+use backend::sitemap::{Sitemap, SitemapConfig};
 use std::{
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
 };
 use tower::util::ServiceExt; // Bring oneshot into scope
 use serde_json::Value;
 
+/// A scratch directory for one test, removed when dropped so that failing
+/// tests clean up too.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("{}-test-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+}
+
+impl std::ops::Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// The full application from [`backend::app`], with defaults for whatever a
+/// test doesn't set.
+struct TestApp {
+    static_dir: PathBuf,
+    api: axum::Router,
+    metrics: Metrics,
+    health: Option<Health>,
+    rate_limiter: RateLimiter,
+}
+
+impl TestApp {
+    /// Serves `static_dir`, with the game API over an empty board.
+    fn new(static_dir: &Path) -> Self {
+        TestApp {
+            static_dir: static_dir.to_path_buf(),
+            api: game_api(),
+            metrics: Metrics::default(),
+            health: None,
+            rate_limiter: RateLimiter::default(),
+        }
+        .game(Arc::new(Mutex::new(GameOfLife::new(vec![0; WIDTH * HEIGHT]))))
+    }
+
+    /// Serves the game API over `game`.
+    fn game(self, game: Arc<Mutex<GameOfLife>>) -> Self {
+        self.api(game_api().layer(axum::extract::Extension(game)))
+    }
+
+    fn api(mut self, api: axum::Router) -> Self {
+        self.api = api;
+        self
+    }
+
+    fn metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    fn health(mut self, health: Health) -> Self {
+        self.health = Some(health);
+        self
+    }
+
+    fn rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    fn build(self) -> axum::Router {
+        let health = self.health.unwrap_or_else(|| Health::new(&self.static_dir));
+        backend::app(
+            self.static_dir.to_str().unwrap(),
+            self.api,
+            Sitemap::new(&self.static_dir, SitemapConfig::default()),
+            self.metrics,
+            health,
+            self.rate_limiter,
+        )
+    }
+}
+
 #[tokio::test]
 async fn test_get_state_endpoint() {
     // Create an initial state.
@@ -87,3 +183,1320 @@ async fn test_toggle_endpoint() {
 
     assert_eq!(response.status(), StatusCode::OK);
 }
+
+#[tokio::test]
+async fn test_undo_redo_endpoints() {
+    let initial_state = vec![0; WIDTH * HEIGHT];
+    let game_state = Arc::new(Mutex::new(GameOfLife::new(initial_state)));
+
+    let app = game_api().layer(axum::extract::Extension(game_state.clone()));
+
+    for uri in ["/toggle?row=1&col=1", "/undo", "/redo", "/undo"] {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(uri)
+                    .method("POST")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    let body_bytes = {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/redo")
+                    .method("POST")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        to_bytes(response.into_body(), usize::MAX).await.unwrap()
+    };
+    let json: Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["grid"][WIDTH + 1], 1);
+
+    // A new action clears the redo stack.
+    let mut gol = game_state.lock().unwrap();
+    gol.undo();
+    gol.step();
+    assert!(!gol.redo());
+}
+
+#[tokio::test]
+async fn test_batch_endpoint_is_one_undo_step() {
+    let initial_state = vec![0; WIDTH * HEIGHT];
+    let game_state = Arc::new(Mutex::new(GameOfLife::new(initial_state)));
+
+    let app = game_api().layer(axum::extract::Extension(game_state.clone()));
+
+    let body = r#"{"cells":[{"row":0,"col":0,"alive":true},{"row":0,"col":1,"alive":true}]}"#;
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/batch")
+                .method("POST")
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let mut gol = game_state.lock().unwrap();
+    assert_eq!(&gol.current[..2], &[1, 1]);
+    assert!(gol.undo());
+    assert_eq!(&gol.current[..2], &[0, 0]);
+}
+
+#[tokio::test]
+async fn test_generation_and_seek_endpoints() {
+    let glider = parse_initial_state(&[
+        "01000000000000000000",
+        "00100000000000000000",
+        "11100000000000000000",
+    ]);
+    let mut initial_state = glider;
+    initial_state.resize(WIDTH * HEIGHT, 0);
+    let game_state = Arc::new(Mutex::new(GameOfLife::new(initial_state)));
+    let expected = {
+        let mut gol = game_state.lock().unwrap();
+        gol.step();
+        let first = gol.current.clone();
+        gol.step();
+        gol.step();
+        first
+    };
+
+    let app = game_api().layer(axum::extract::Extension(game_state.clone()));
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/generation/1")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["generation"], 1);
+    assert_eq!(json["grid"], serde_json::to_value(&expected).unwrap());
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/seek?gen=1")
+                .method("POST")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    {
+        let gol = game_state.lock().unwrap();
+        assert_eq!(gol.generation, 1);
+        assert_eq!(gol.current, expected);
+    }
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/seek?gen=99")
+                .method("POST")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn test_timeline_recomputes_pruned_generations() {
+    let mut initial_state = parse_initial_state(&[
+        "01000000000000000000",
+        "00100000000000000000",
+        "11100000000000000000",
+    ]);
+    initial_state.resize(WIDTH * HEIGHT, 0);
+    let mut gol = GameOfLife::new(initial_state);
+
+    let mut boards = vec![gol.current.clone()];
+    for _ in 0..RETAINED_SNAPSHOTS * 2 {
+        gol.step();
+        boards.push(gol.current.clone());
+    }
+
+    for (generation, board) in boards.iter().enumerate() {
+        assert_eq!(gol.generation_board(generation).as_ref(), Some(board));
+    }
+}
+
+#[test]
+fn test_long_runs_stay_bounded() {
+    let mut initial_state = parse_initial_state(&[
+        "01000000000000000000",
+        "00100000000000000000",
+        "11100000000000000000",
+    ]);
+    initial_state.resize(WIDTH * HEIGHT, 0);
+    let mut gol = GameOfLife::new(initial_state);
+
+    let mut boards = vec![gol.current.clone()];
+    for _ in 0..MAX_TIMELINE + RETAINED_SNAPSHOTS * 2 {
+        gol.step();
+        boards.push(gol.current.clone());
+    }
+
+    let latest = gol.generation;
+    assert_eq!(gol.history.len(), MAX_HISTORY);
+    assert_eq!(gol.timeline.origin(), latest - MAX_TIMELINE);
+    assert!(
+        gol.timeline.stored_boards() <= RETAINED_SNAPSHOTS + MAX_TIMELINE / CHECKPOINT_INTERVAL + 2,
+        "{} boards stored",
+        gol.timeline.stored_boards()
+    );
+
+    // Generations whose snapshots were thinned out are recomputed exactly.
+    assert_eq!(gol.generation_board(gol.timeline.origin() - 1), None);
+    for generation in (gol.timeline.origin()..=latest).step_by(CHECKPOINT_INTERVAL - 3).chain([latest]) {
+        assert_eq!(gol.generation_board(generation).as_ref(), Some(&boards[generation]), "generation {}", generation);
+    }
+
+    // Undo reaches back exactly as far as the history was kept.
+    for _ in 0..MAX_HISTORY {
+        assert!(gol.undo());
+    }
+    assert!(!gol.undo());
+    assert_eq!(gol.generation, latest - MAX_HISTORY);
+    assert_eq!(gol.current, boards[latest - MAX_HISTORY]);
+    assert_eq!(gol.redo.len(), MAX_HISTORY);
+}
+
+#[tokio::test]
+async fn test_state_format_negotiation() {
+    let mut initial_state = vec![0; WIDTH * HEIGHT];
+    initial_state[0] = 1;
+    initial_state[WIDTH + 2] = 1;
+    let game_state = Arc::new(Mutex::new(GameOfLife::new(initial_state.clone())));
+
+    let app = game_api().layer(axum::extract::Extension(game_state));
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/state")
+                .header("accept", "application/octet-stream")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/octet-stream");
+    assert_eq!(response.headers()["x-grid-width"], WIDTH.to_string().as_str());
+    let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(unpack_bits(&body_bytes, WIDTH * HEIGHT), initial_state);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/state?format=packed")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["encoding"], "bitpacked-base64");
+    assert_eq!(json["data"].as_str().unwrap().len(), (WIDTH * HEIGHT).div_ceil(8).div_ceil(3) * 4);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/step?format=rle")
+                .method("POST")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/state?format=bogus")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[test]
+fn test_rle_encoding() {
+    let mut grid = vec![0; WIDTH * HEIGHT];
+    grid[WIDTH + 1] = 1;
+    grid[2 * WIDTH + 2] = 1;
+    grid[3 * WIDTH] = 1;
+    grid[3 * WIDTH + 1] = 1;
+    grid[3 * WIDTH + 2] = 1;
+    assert_eq!(
+        encode_rle(&grid, WIDTH, HEIGHT),
+        format!("x = {}, y = {}, rule = B3/S23\n$bo$2bo$3o!\n", WIDTH, HEIGHT)
+    );
+}
+
+#[tokio::test]
+async fn test_state_viewport_and_overview() {
+    let mut initial_state = vec![0; WIDTH * HEIGHT];
+    initial_state[2 * WIDTH + 3] = 1;
+    let game_state = Arc::new(Mutex::new(GameOfLife::new(initial_state)));
+
+    let app = game_api().layer(axum::extract::Extension(game_state));
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/state?x=2&y=1&w=3&h=2")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["width"], 3);
+    assert_eq!(json["height"], 2);
+    assert_eq!(json["grid"], serde_json::json!([0, 0, 0, 0, 1, 0]));
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/state?x={}&w=2", WIDTH - 1))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Sizes that would overflow `x + w` are rejected rather than wrapping.
+    for uri in [format!("/state?x=1&w={}", usize::MAX), format!("/state?y=1&h={}", usize::MAX)] {
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/overview?block=8")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["width"], WIDTH.div_ceil(8));
+    assert_eq!(json["density"][0], 1.0 / 64.0);
+}
+
+#[tokio::test]
+async fn test_image_endpoints() {
+    let mut initial_state = vec![0; WIDTH * HEIGHT];
+    initial_state[0] = 1;
+    let game_state = Arc::new(Mutex::new(GameOfLife::new(initial_state)));
+
+    let app = game_api().layer(axum::extract::Extension(game_state));
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/image.png?cell=4&grid=true")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "image/png");
+    let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(&body_bytes[1..4], b"PNG");
+    // IHDR width and height follow the signature and chunk header.
+    let dimension = (WIDTH * 4 + 1) as u32;
+    assert_eq!(&body_bytes[16..20], &dimension.to_be_bytes());
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/image.svg?cell=5&alive=ff0000")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let svg = String::from_utf8(body_bytes.to_vec()).unwrap();
+    assert!(svg.contains(r##"<g fill="#ff0000"><rect x="0" y="0" width="5" height="5"/></g>"##));
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/image.png?cell=1000")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // One pixel cells would be all grid line.
+    for uri in ["/image.png?cell=1&grid=true", "/image.svg?cell=1&grid=true", "/animation.gif?cell=1&grid=true"] {
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", uri);
+    }
+}
+
+#[tokio::test]
+async fn test_animation_gif_endpoint() {
+    let initial_state = vec![0; WIDTH * HEIGHT];
+    let game_state = Arc::new(Mutex::new(GameOfLife::new(initial_state)));
+
+    let app = game_api().layer(axum::extract::Extension(game_state.clone()));
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/animation.gif?generations=5&cell=2")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "image/gif");
+    let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(&body_bytes[..6], b"GIF89a");
+    assert_eq!(game_state.lock().unwrap().generation, 0);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/animation.gif?generations=100000")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_sitemap_scans_static_dir() {
+    use backend::sitemap::PageOverride;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let root = TempDir::new("sitemap");
+    std::fs::create_dir_all(root.join("blog")).unwrap();
+    for page in ["index.html", "homelab.html", "404.html", "blog/index.html", "style.css"] {
+        let file = std::fs::File::create(root.join(page)).unwrap();
+        // 2024-03-15
+        file.set_modified(UNIX_EPOCH + Duration::from_secs(1_710_460_800)).unwrap();
+    }
+
+    let mut config = SitemapConfig {
+        base_url: "https://example.com/".to_string(),
+        ..SitemapConfig::default()
+    };
+    config.overrides.insert(
+        "homelab.html".to_string(),
+        PageOverride {
+            priority: Some(0.9),
+            changefreq: Some("weekly".to_string()),
+        },
+    );
+    let sitemap = Sitemap::new(&*root, config);
+
+    let app = axum::Router::new()
+        .route("/sitemap.xml", axum::routing::get(backend::routes::sitemap))
+        .layer(axum::extract::Extension(sitemap.clone()));
+    let response = app
+        .oneshot(Request::builder().uri("/sitemap.xml").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/xml");
+    let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let xml = String::from_utf8(body_bytes.to_vec()).unwrap();
+
+    assert!(xml.contains("<loc>https://example.com/</loc>"));
+    assert!(xml.contains("<loc>https://example.com/blog/</loc>"));
+    assert!(xml.contains("<lastmod>2024-03-15</lastmod>"));
+    assert!(!xml.contains("404.html"));
+    assert!(!xml.contains("style.css"));
+    let homelab = xml.find("https://example.com/homelab.html").unwrap();
+    assert!(xml[homelab..].contains("<changefreq>weekly</changefreq>\n        <priority>0.9</priority>"));
+    assert!(xml.find("https://example.com/</loc>").unwrap() < homelab);
+
+    assert!(!sitemap.refresh());
+    std::fs::write(root.join("about.html"), "").unwrap();
+    assert!(sitemap.refresh());
+    assert!(sitemap.xml().contains("<loc>https://example.com/about.html</loc>"));
+}
+
+#[tokio::test]
+async fn test_error_pages() {
+    use axum::extract::ConnectInfo;
+    use std::net::SocketAddr;
+
+    let root = TempDir::new("error-pages");
+
+    async fn failing() -> (StatusCode, &'static str) {
+        (StatusCode::INTERNAL_SERVER_ERROR, "board unavailable")
+    }
+    let game_state = Arc::new(Mutex::new(GameOfLife::new(vec![0; WIDTH * HEIGHT])));
+    let api = game_api()
+        .route("/fail", axum::routing::get(failing))
+        .layer(axum::extract::Extension(game_state));
+    let app = TestApp::new(&root).api(api).build();
+    let request = |uri: &str| {
+        Request::builder()
+            .uri(uri)
+            .extension(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 4000))))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(request("/missing.html")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/html"));
+    let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let html = String::from_utf8(body_bytes.to_vec()).unwrap();
+    assert!(html.contains("<title>404 Not Found | Ryan Rumana</title>"), "{}", html);
+    assert!(!html.contains("{{"), "{}", html);
+
+    let response = app.clone().oneshot(request("/api/game-of-life/missing")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["status"], 404);
+    assert_eq!(json["error"], "Not Found");
+
+    let response = app.clone().oneshot(request("/api/game-of-life/state?format=bogus")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["message"], "Unknown format: bogus");
+
+    let response = app.oneshot(request("/api/game-of-life/fail")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("application/json"));
+    let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["status"], 500);
+    assert_eq!(json["error"], "Internal Server Error");
+    assert_eq!(json["message"], "board unavailable");
+
+    // The template isn't among the site's static files.
+    let site = TestApp::new(Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../static"))).build();
+    let response = site.oneshot(request("/error.html")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let html = String::from_utf8(body_bytes.to_vec()).unwrap();
+    assert!(html.contains("<title>404 Not Found | Ryan Rumana</title>"), "{}", html);
+}
+
+#[tokio::test]
+async fn test_static_caching_and_compression() {
+    use axum::extract::ConnectInfo;
+    use std::net::SocketAddr;
+
+    let root = TempDir::new("static-cache");
+    std::fs::write(root.join("index.html"), "<p>hello</p>".repeat(100)).unwrap();
+    std::fs::write(root.join("app.3f9dc80a.js"), "console.log(1);").unwrap();
+    std::fs::write(root.join("life.wasm"), [0u8; 16]).unwrap();
+    std::fs::write(root.join("life.js"), "export default init;").unwrap();
+    std::fs::write(root.join("life.3f9dc80a.wasm"), [0u8; 16]).unwrap();
+    std::fs::write(root.join("logo.png"), [0u8; 16]).unwrap();
+    std::fs::write(root.join("data.json"), "{}").unwrap();
+    std::fs::write(root.join("data.json.gz"), b"precompressed").unwrap();
+    let app = TestApp::new(&root).build();
+    let request = |uri: &str, headers: &[(&str, &str)]| {
+        let mut builder = Request::builder()
+            .uri(uri)
+            .extension(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 4000))));
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+
+    let response = app.clone().oneshot(request("/index.html", &[])).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["cache-control"], "public, max-age=300, must-revalidate");
+    assert!(response.headers().contains_key("last-modified"));
+    let etag = response.headers()["etag"].to_str().unwrap().to_string();
+    assert!(etag.starts_with("W/\""));
+
+    let response = app
+        .clone()
+        .oneshot(request("/index.html", &[("if-none-match", &etag)]))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers()["etag"], etag.as_str());
+    let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert!(body_bytes.is_empty());
+
+    let response = app.clone().oneshot(request("/app.3f9dc80a.js", &[])).await.unwrap();
+    assert_eq!(response.headers()["cache-control"], "public, max-age=31536000, immutable");
+    let response = app.clone().oneshot(request("/logo.png", &[])).await.unwrap();
+    assert_eq!(response.headers()["cache-control"], "public, max-age=2592000");
+    // An unhashed module expires together with its glue code.
+    for path in ["/life.wasm", "/life.js"] {
+        let response = app.clone().oneshot(request(path, &[])).await.unwrap();
+        assert_eq!(response.headers()["cache-control"], "public, max-age=86400", "{}", path);
+    }
+    let response = app.clone().oneshot(request("/life.3f9dc80a.wasm", &[])).await.unwrap();
+    assert_eq!(response.headers()["cache-control"], "public, max-age=31536000, immutable");
+
+    let response = app
+        .clone()
+        .oneshot(request("/data.json", &[("accept-encoding", "gzip")]))
+        .await
+        .unwrap();
+    assert_eq!(response.headers()["content-encoding"], "gzip");
+    let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(&body_bytes[..], b"precompressed");
+
+    let response = app
+        .clone()
+        .oneshot(request("/index.html", &[("accept-encoding", "br")]))
+        .await
+        .unwrap();
+    assert_eq!(response.headers()["content-encoding"], "br");
+
+    let response = app.oneshot(request("/missing.html", &[])).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(!response.headers().contains_key("cache-control"));
+}
+
+#[tokio::test]
+async fn test_security_headers() {
+    use axum::extract::ConnectInfo;
+    use backend::security::{security_headers, SecurityHeaders, SecurityPolicy};
+    use std::net::SocketAddr;
+
+    let root = TempDir::new("security-headers");
+    std::fs::write(root.join("index.html"), "<p>hello</p>").unwrap();
+    let app = TestApp::new(&root).build();
+    let request = |uri: &str| {
+        Request::builder()
+            .uri(uri)
+            .extension(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 4000))))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    for uri in ["/index.html", "/missing.html", "/api/game-of-life/state"] {
+        let response = app.clone().oneshot(request(uri)).await.unwrap();
+        let headers = response.headers();
+        assert_eq!(headers["x-content-type-options"], "nosniff", "{}", uri);
+        assert!(headers["strict-transport-security"].to_str().unwrap().starts_with("max-age="));
+        assert!(headers.contains_key("referrer-policy"));
+        assert!(headers.contains_key("permissions-policy"));
+        let csp = headers["content-security-policy"].to_str().unwrap();
+        assert!(csp.contains("frame-ancestors 'none'"), "{}", uri);
+        if uri.starts_with("/api/") {
+            assert!(csp.starts_with("default-src 'none'"));
+        } else {
+            assert!(csp.contains("'wasm-unsafe-eval'"));
+        }
+    }
+
+    let config = SecurityHeaders::new(SecurityPolicy::site()).route(
+        "/embed/",
+        SecurityPolicy {
+            frame_ancestors: Some("https://example.com".to_string()),
+            strict_transport_security: None,
+            ..SecurityPolicy::site()
+        },
+    );
+    let router = axum::Router::new()
+        .route("/embed/life", axum::routing::get(|| async { "embedded" }))
+        .route("/page", axum::routing::get(|| async { "page" }))
+        .layer(axum::middleware::from_fn_with_state(Arc::new(config), security_headers));
+    let response = router.clone().oneshot(request("/embed/life")).await.unwrap();
+    let csp = response.headers()["content-security-policy"].to_str().unwrap();
+    assert!(csp.ends_with("frame-ancestors https://example.com"));
+    assert!(!response.headers().contains_key("strict-transport-security"));
+    let response = router.oneshot(request("/page")).await.unwrap();
+    assert!(response.headers().contains_key("strict-transport-security"));
+}
+
+#[tokio::test]
+async fn test_rate_limiting() {
+    use axum::extract::ConnectInfo;
+    use std::net::SocketAddr;
+
+    let root = TempDir::new("rate-limit");
+    let app = TestApp::new(&root).build();
+    let reset = |peer: [u8; 4], forwarded_for: Option<&str>| {
+        let mut builder = Request::builder()
+            .method("POST")
+            .uri("/api/game-of-life/reset")
+            .extension(ConnectInfo(SocketAddr::from((peer, 4000))));
+        if let Some(forwarded_for) = forwarded_for {
+            builder = builder.header("x-forwarded-for", forwarded_for);
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+
+    for _ in 0..5 {
+        let response = app.clone().oneshot(reset([10, 0, 0, 1], None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    let response = app.clone().oneshot(reset([10, 0, 0, 1], None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = response.headers()["retry-after"].to_str().unwrap().parse().unwrap();
+    assert!(retry_after >= 1);
+    let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["status"], 429);
+
+    // Spoofed headers from an untrusted peer are ignored.
+    let response = app.clone().oneshot(reset([10, 0, 0, 1], Some("10.0.0.2"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // Behind a trusted local proxy, requests count against the rightmost
+    // untrusted forwarded address.
+    for _ in 0..2 {
+        let response = app
+            .clone()
+            .oneshot(reset([127, 0, 0, 1], Some("1.2.3.4, 10.0.0.1")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+    let response = app.clone().oneshot(reset([127, 0, 0, 1], Some("10.0.0.3"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = |method: &str, uri: &str, body: &str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .extension(ConnectInfo(SocketAddr::from(([10, 0, 0, 9], 4000))))
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    // Toggling and batch edits share a bucket, so one can't stand in for
+    // the other once the limit is reached.
+    for _ in 0..40 {
+        let response = app.clone().oneshot(request("POST", "/api/game-of-life/toggle?row=1&col=1", "")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    let batch = r#"{"cells": [{"row": 1, "col": 1, "alive": true}]}"#;
+    let response = app.clone().oneshot(request("POST", "/api/game-of-life/batch", batch)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // So do stepping and seeking.
+    for _ in 0..19 {
+        let response = app.clone().oneshot(request("POST", "/api/game-of-life/step", "")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    let response = app.clone().oneshot(request("POST", "/api/game-of-life/seek?gen=0", "")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.clone().oneshot(request("POST", "/api/game-of-life/seek?gen=0", "")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // GIF rendering has its own, tighter limit.
+    for _ in 0..3 {
+        let response = app
+            .clone()
+            .oneshot(request("GET", "/api/game-of-life/animation.gif?generations=2", ""))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    let response = app
+        .clone()
+        .oneshot(request("GET", "/api/game-of-life/animation.gif?generations=2", ""))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // Routes without a limit are unaffected.
+    let response = app.oneshot(request("GET", "/api/game-of-life/state", "")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Overrides change a whole group's limit, or limit a route on its own.
+    let limiter = RateLimiter::default()
+        .with_overrides("/api/game-of-life/undo=2/0.01, /api/game-of-life/state=1/0.01, bogus, /x=0/1");
+    let app = TestApp::new(&root).rate_limiter(limiter).build();
+    let statuses = |requests: Vec<(&'static str, &'static str)>| {
+        let app = app.clone();
+        async move {
+            let mut statuses = Vec::new();
+            for (method, uri) in requests {
+                statuses.push(app.clone().oneshot(request(method, uri, "")).await.unwrap().status().as_u16());
+            }
+            statuses
+        }
+    };
+    let step = ("POST", "/api/game-of-life/step");
+    assert_eq!(statuses(vec![step, step, step]).await, [200, 200, 429]);
+    let state = ("GET", "/api/game-of-life/state");
+    assert_eq!(statuses(vec![state, state]).await, [200, 429]);
+    assert_eq!(RateLimit::parse("20/10"), Some(RateLimit::new(20, 10.0)));
+    for invalid in ["20", "0/1", "1/0", "x/1", "1/inf"] {
+        assert_eq!(RateLimit::parse(invalid), None, "{}", invalid);
+    }
+}
+
+#[tokio::test]
+async fn test_client_ip_resolution() {
+    use axum::extract::ConnectInfo;
+    use axum::http::HeaderMap;
+    use backend::client_ip::{resolve_client_ip, ClientIp, IpRange, TrustedProxies};
+    use std::net::{IpAddr, SocketAddr};
+
+    let trusted = TrustedProxies::default().trust(IpRange::parse("173.245.48.0/20").unwrap());
+    let resolve = |peer: &str, headers: &[(&'static str, &str)]| {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.append(*name, value.parse().unwrap());
+        }
+        trusted.resolve(peer.parse().unwrap(), &map).to_string()
+    };
+
+    // Untrusted peers are the client, whatever they claim.
+    assert_eq!(resolve("203.0.113.9", &[("x-forwarded-for", "1.2.3.4")]), "203.0.113.9");
+    assert_eq!(resolve("127.0.0.1", &[]), "127.0.0.1");
+    // The rightmost untrusted hop wins, skipping trusted proxies in the chain.
+    assert_eq!(
+        resolve("127.0.0.1", &[("x-forwarded-for", "9.9.9.9, 198.51.100.7, 173.245.48.10")]),
+        "198.51.100.7"
+    );
+    assert_eq!(
+        resolve("::1", &[("forwarded", r#"for=192.0.2.60;proto=https, for="[2001:db8::17]:4711""#)]),
+        "2001:db8::17"
+    );
+    assert_eq!(resolve("127.0.0.1", &[("forwarded", "for=unknown"), ("x-real-ip", "192.0.2.1")]), "192.0.2.1");
+    assert_eq!(resolve("127.0.0.1", &[("cf-connecting-ip", "192.0.2.44")]), "192.0.2.44");
+    assert_eq!(
+        TrustedProxies::none()
+            .resolve("127.0.0.1".parse().unwrap(), &HeaderMap::new())
+            .to_string(),
+        "127.0.0.1"
+    );
+
+    let router = axum::Router::new()
+        .route(
+            "/ip",
+            axum::routing::get(|axum::extract::Extension(ClientIp(ip)): axum::extract::Extension<ClientIp>| async move {
+                ip.to_string()
+            }),
+        )
+        .layer(axum::middleware::from_fn_with_state(Arc::new(TrustedProxies::default()), resolve_client_ip));
+    let request = Request::builder()
+        .uri("/ip")
+        .header("x-forwarded-for", "198.51.100.7")
+        .extension(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 4000))))
+        .body(Body::empty())
+        .unwrap();
+    let response = router.oneshot(request).await.unwrap();
+    let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(std::str::from_utf8(&body_bytes).unwrap().parse::<IpAddr>().unwrap(), "198.51.100.7".parse::<IpAddr>().unwrap());
+}
+
+#[tokio::test]
+async fn test_request_ids_and_access_log_formats() {
+    use axum::extract::ConnectInfo;
+    use backend::middleware::{AccessLogFormat, AccessRecord};
+    use std::net::SocketAddr;
+
+    let root = TempDir::new("request-id");
+    let app = TestApp::new(&root).build();
+    let request = |request_id: Option<&str>| {
+        let mut builder = Request::builder()
+            .uri("/api/game-of-life/state")
+            .extension(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 4000))));
+        if let Some(request_id) = request_id {
+            builder = builder.header("x-request-id", request_id);
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+
+    let response = app.clone().oneshot(request(None)).await.unwrap();
+    let generated = response.headers()["x-request-id"].to_str().unwrap();
+    assert_eq!(generated.len(), 36);
+    let response = app.oneshot(request(Some("trace-123"))).await.unwrap();
+    assert_eq!(response.headers()["x-request-id"], "trace-123");
+
+    let record = AccessRecord {
+        timestamp: "2026-10-19T08:09:10.123Z".to_string(),
+        request_id: "trace-123".to_string(),
+        ip: "198.51.100.7".to_string(),
+        method: "GET".to_string(),
+        path: "/index.html".to_string(),
+        query: Some("a=1".to_string()),
+        protocol: "HTTP/1.1".to_string(),
+        status: 200,
+        bytes_sent: 512,
+        ttfb_us: 800,
+        latency_us: 1500,
+        user_agent: "Mozilla/5.0 \"test\"".to_string(),
+        referer: "-".to_string(),
+        cache: Some("hit"),
+        precompressed: false,
+        aborted: false,
+    };
+    assert_eq!(
+        record.format(AccessLogFormat::Combined),
+        r#"198.51.100.7 - - [19/Oct/2026:08:09:10 +0000] "GET /index.html?a=1 HTTP/1.1" 200 512 "-" "Mozilla/5.0 \"test\"""#
+    );
+    let json: Value = serde_json::from_str(&record.format(AccessLogFormat::Json)).unwrap();
+    assert_eq!(json["request_id"], "trace-123");
+    assert_eq!(json["latency_us"], 1500);
+    assert_eq!(json["bytes_sent"], 512);
+    // The text line keeps its original layout, with the transfer details
+    // after it.
+    let local = chrono::DateTime::parse_from_rfc3339(&record.timestamp)
+        .unwrap()
+        .with_timezone(&chrono::Local)
+        .format("%+");
+    assert_eq!(
+        record.format(AccessLogFormat::Text),
+        format!(
+            r#"[{}] 198.51.100.7 GET /index.html?a=1 200 "Mozilla/5.0 "test"" "-" 1ms bytes_sent=512 ttfb_us=800 latency_us=1500 cache=hit"#,
+            local
+        )
+    );
+    let record = AccessRecord {
+        cache: None,
+        precompressed: true,
+        aborted: true,
+        ..record
+    };
+    assert!(
+        record
+            .format(AccessLogFormat::Text)
+            .ends_with(r#""-" 1ms bytes_sent=512 ttfb_us=800 latency_us=1500 cache=- precompressed=true aborted=true"#)
+    );
+}
+
+/// Collects `access` log lines so tests can inspect them.
+struct AccessLogCapture(Mutex<Vec<String>>);
+
+impl log::Log for AccessLogCapture {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.target() == "access"
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static ACCESS_LOG: AccessLogCapture = AccessLogCapture(Mutex::new(Vec::new()));
+
+/// The access appender's pattern is just `{m}{n}`, so any other writer to
+/// its target would produce lines without a timestamp, in no known format.
+#[test]
+fn test_only_access_records_use_the_access_target() {
+    let src = std::path::Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/src"));
+    for entry in std::fs::read_dir(src).unwrap() {
+        let path = entry.unwrap().path();
+        let source = std::fs::read_to_string(&path).unwrap();
+        let writes = source.matches("target: \"access\"").count() + source.matches("target: ACCESS_TARGET").count();
+        let expected = if path.ends_with("middleware.rs") { 1 } else { 0 };
+        assert_eq!(writes, expected, "{}", path.display());
+    }
+    let config = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/../log4rs.yaml")).unwrap();
+    assert!(config.contains("pattern: \"{m}{n}\""));
+}
+
+fn access_log_line(needle: &str) -> Option<String> {
+    ACCESS_LOG
+        .0
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|line| line.contains(needle))
+        .cloned()
+}
+
+#[tokio::test]
+async fn test_access_log_bytes_timing_and_cache_flags() {
+    use axum::extract::ConnectInfo;
+    use axum::middleware::from_fn_with_state;
+    use axum::routing::get_service;
+    use backend::cache::{static_headers, CachePolicy};
+    use backend::middleware::{log_requests, AccessLog, AccessLogFormat};
+    use http_body::Body as _;
+    use std::net::SocketAddr;
+    use tower_http::request_id::{MakeRequestUuid, SetRequestIdLayer};
+    use tower_http::services::ServeDir;
+
+    if log::set_logger(&ACCESS_LOG).is_ok() {
+        log::set_max_level(log::LevelFilter::Info);
+    }
+
+    let root = TempDir::new("access-log");
+    std::fs::write(root.join("page.html"), "x".repeat(1000)).unwrap();
+    let game_state = Arc::new(Mutex::new(GameOfLife::new(vec![0; WIDTH * HEIGHT])));
+    let static_files = get_service(ServeDir::new(&*root)).layer(from_fn_with_state(CachePolicy::default(), static_headers));
+    let access_log = AccessLog {
+        format: AccessLogFormat::Json,
+        ..AccessLog::default()
+    };
+    // A body that only knows it has ended once polled past its last chunk.
+    struct Chunks(Vec<&'static str>);
+    impl http_body::Body for Chunks {
+        type Data = axum::body::Bytes;
+        type Error = std::convert::Infallible;
+
+        fn poll_frame(
+            mut self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<Option<Result<http_body::Frame<Self::Data>, Self::Error>>> {
+            let chunk = (!self.0.is_empty()).then(|| self.0.remove(0));
+            std::task::Poll::Ready(chunk.map(|chunk| Ok(http_body::Frame::data(chunk.into()))))
+        }
+    }
+    let streamed = || async { ([("content-length", "6")], Body::new(Chunks(vec!["abc", "def"]))) };
+    let limiter = RateLimiter::new().route("/limited", RateLimit::new(1, 0.01));
+    let app = axum::Router::new()
+        .route("/streamed", axum::routing::get(streamed))
+        .route("/limited", axum::routing::get(|| async { "ok" }))
+        .nest("/api/game-of-life", game_api().layer(axum::extract::Extension(game_state)))
+        .fallback_service(static_files)
+        .layer(from_fn_with_state(Arc::new(limiter), backend::rate_limit::rate_limit))
+        .layer(from_fn_with_state(Arc::new(access_log), log_requests))
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid));
+    let request = |uri: &str, headers: &[(&str, &str)]| {
+        let mut builder = Request::builder()
+            .uri(uri)
+            .extension(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 4000))));
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+    let record = |request_id: &str| -> Option<Value> {
+        access_log_line(&format!("\"request_id\":\"{}\"", request_id)).map(|line| serde_json::from_str(&line).unwrap())
+    };
+
+    let response = app
+        .clone()
+        .oneshot(request("/page.html", &[("x-request-id", "log-static-miss")]))
+        .await
+        .unwrap();
+    let etag = response.headers()["etag"].to_str().unwrap().to_string();
+    // Logged only once the body has been streamed.
+    assert!(record("log-static-miss").is_none());
+    let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(body_bytes.len(), 1000);
+    let logged = record("log-static-miss").unwrap();
+    assert_eq!(logged["bytes_sent"], 1000);
+    assert_eq!(logged["cache"], "miss");
+    assert!(logged.get("aborted").is_none(), "{}", logged);
+    assert!(logged["ttfb_us"].as_u64().unwrap() <= logged["latency_us"].as_u64().unwrap());
+
+    let response = app
+        .clone()
+        .oneshot(request("/page.html", &[("x-request-id", "log-static-hit"), ("if-none-match", &etag)]))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    drop(response);
+    let logged = record("log-static-hit").unwrap();
+    assert_eq!(logged["status"], 304);
+    assert_eq!(logged["bytes_sent"], 0);
+    assert_eq!(logged["cache"], "hit");
+
+    let response = app
+        .clone()
+        .oneshot(request("/api/game-of-life/state", &[("x-request-id", "log-api")]))
+        .await
+        .unwrap();
+    let body_bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let logged = record("log-api").unwrap();
+    assert_eq!(logged["bytes_sent"], body_bytes.len());
+    assert!(logged.get("cache").is_none(), "{}", logged);
+
+    // Like hyper, stop reading a streamed body once its declared length has
+    // arrived, without polling for the end of the stream.
+    let read_declared = |request_id: &'static str, limit: usize| {
+        let app = app.clone();
+        async move {
+            let response = app.oneshot(request("/streamed", &[("x-request-id", request_id)])).await.unwrap();
+            let mut body = response.into_body();
+            let mut read = 0;
+            while read < limit {
+                let frame = std::future::poll_fn(|cx| std::pin::Pin::new(&mut body).poll_frame(cx)).await;
+                read += frame.unwrap().unwrap().into_data().unwrap().len();
+            }
+        }
+    };
+    read_declared("log-streamed", 6).await;
+    let logged = record("log-streamed").unwrap();
+    assert_eq!(logged["bytes_sent"], 6);
+    assert!(logged.get("aborted").is_none(), "{}", logged);
+
+    // A client that goes away part way through is still caught.
+    read_declared("log-streamed-abort", 3).await;
+    let logged = record("log-streamed-abort").unwrap();
+    assert_eq!(logged["bytes_sent"], 3);
+    assert_eq!(logged["aborted"], true);
+
+
+    // Nothing else writes to the access target, so rate limited requests
+    // keep the stream one JSON object per line.
+    for _ in 0..3 {
+        let request = Request::builder()
+            .uri("/limited")
+            .extension(ConnectInfo(SocketAddr::from(([203, 0, 113, 77], 4000))))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    }
+    let lines: Vec<String> = ACCESS_LOG.0.lock().unwrap().iter().filter(|line| line.contains("203.0.113.77")).cloned().collect();
+    let statuses: Vec<Value> = lines
+        .iter()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap_or_else(|e| panic!("{}: {}", e, line))["status"].clone())
+        .collect();
+    assert_eq!(statuses, [200, 429, 429]);
+}
+
+#[tokio::test]
+async fn test_metrics_endpoint() {
+    use axum::extract::ConnectInfo;
+    use std::net::SocketAddr;
+
+    let root = TempDir::new("metrics");
+    let mut initial = vec![0; WIDTH * HEIGHT];
+    initial[1] = 1;
+    initial[21] = 1;
+    initial[41] = 1;
+    let game_state = Arc::new(Mutex::new(GameOfLife::new(initial)));
+    let metrics = Metrics::default().with_game(game_state.clone());
+    let app = TestApp::new(&root).game(game_state).metrics(metrics).build();
+    let request = |method: &str, uri: &str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .extension(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 4000))))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    for _ in 0..2 {
+        let response = app.clone().oneshot(request("POST", "/api/game-of-life/step")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    app.clone().oneshot(request("GET", "/api/game-of-life/generation/1")).await.unwrap();
+    app.clone().oneshot(request("GET", "/api/game-of-life/generation/2")).await.unwrap();
+    app.clone().oneshot(request("GET", "/api/game-of-life/missing")).await.unwrap();
+    app.clone().oneshot(request("GET", "/missing.css")).await.unwrap();
+    for method in ["X0", "X1", "X2"] {
+        app.clone().oneshot(request(method, "/anything")).await.unwrap();
+    }
+
+    let response = app.clone().oneshot(request("GET", "/metrics")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/plain; version=0.0.4"));
+    let body = String::from_utf8(to_bytes(response.into_body(), usize::MAX).await.unwrap().to_vec()).unwrap();
+
+    assert!(body.contains(
+        "http_requests_total{route=\"/api/game-of-life/step\",method=\"POST\",status=\"200\"} 2"
+    ), "{}", body);
+    // Path parameters share one series.
+    assert!(body.contains(
+        "http_requests_total{route=\"/api/game-of-life/generation/{n}\",method=\"GET\",status=\"200\"} 2"
+    ), "{}", body);
+    assert!(body.contains("http_requests_total{route=\"unmatched\",method=\"GET\",status=\"404\"} 1"), "{}", body);
+    assert!(body.contains("http_requests_total{route=\"static\",method=\"GET\",status=\"404\"} 1"), "{}", body);
+    // Made-up methods share one series.
+    assert!(body.contains("http_requests_total{route=\"static\",method=\"other\",status=\"405\"} 3"), "{}", body);
+    assert!(!body.contains("method=\"X0\""), "{}", body);
+    assert!(body.contains(
+        "http_request_duration_seconds_bucket{route=\"/api/game-of-life/step\",method=\"POST\",status=\"200\",le=\"+Inf\"} 2"
+    ), "{}", body);
+    assert!(body.contains(
+        "http_request_duration_seconds_count{route=\"/api/game-of-life/step\",method=\"POST\",status=\"200\"} 2"
+    ), "{}", body);
+    assert!(body.contains("# TYPE http_request_duration_seconds histogram"), "{}", body);
+    // The scrape itself is in flight.
+    assert!(body.contains("http_requests_in_flight 1\n"), "{}", body);
+    assert!(body.contains("game_of_life_generation 2\n"), "{}", body);
+    // The blinker oscillates, keeping three cells alive.
+    assert!(body.contains("game_of_life_population 3\n"), "{}", body);
+    assert!(body.contains("game_of_life_history_length 2\n"), "{}", body);
+    assert!(body.contains("game_of_life_steps_total 2\n"), "{}", body);
+    assert!(body.contains("game_of_life_steps_per_second "), "{}", body);
+    assert!(body.contains("process_uptime_seconds "), "{}", body);
+
+    // Remote clients are refused unless the endpoint is opened up.
+    let with_metrics = |metrics: Metrics| TestApp::new(&root).metrics(metrics).build();
+    let from = |ip: [u8; 4]| {
+        Request::builder()
+            .uri("/metrics")
+            .extension(ConnectInfo(SocketAddr::from((ip, 4000))))
+            .body(Body::empty())
+            .unwrap()
+    };
+    let restricted = with_metrics(Metrics::default());
+    let response = restricted.clone().oneshot(from([203, 0, 113, 9])).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = restricted.oneshot(from([127, 0, 0, 1])).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let open = with_metrics(Metrics::default().localhost_only(false));
+    let response = open.oneshot(from([203, 0, 113, 9])).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_health_and_readiness() {
+    use axum::extract::ConnectInfo;
+    use std::net::SocketAddr;
+    use std::time::Duration;
+
+    if log::set_logger(&ACCESS_LOG).is_ok() {
+        log::set_max_level(log::LevelFilter::Info);
+    }
+
+    let root = TempDir::new("health");
+    std::fs::write(root.join("app.log"), "").unwrap();
+    let game_state = Arc::new(Mutex::new(GameOfLife::new(vec![0; WIDTH * HEIGHT])));
+    let build = |static_dir: &Path| {
+        let mut health = Health::new(static_dir)
+            .with_game(game_state.clone())
+            .log_files([root.join("app.log")]);
+        health.lock_timeout = Duration::from_millis(20);
+        TestApp::new(&root).game(game_state.clone()).health(health).build()
+    };
+    let request = |uri: &str, request_id: &str| {
+        Request::builder()
+            .uri(uri)
+            .header("x-request-id", request_id)
+            .extension(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 4000))))
+            .body(Body::empty())
+            .unwrap()
+    };
+    let json = |response: axum::response::Response| async move {
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice::<Value>(&body).unwrap()
+    };
+    let app = build(&root);
+
+    let response = app.clone().oneshot(request("/healthz?probe=health-live", "health-live")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = json(response).await;
+    assert_eq!(body["status"], "ok");
+    assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+    assert!(body["commit"].is_string());
+    assert!(body["uptime_seconds"].as_f64().unwrap() >= 0.0);
+
+    let response = app.clone().oneshot(request("/readyz?probe=health-ready", "health-ready")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = json(response).await;
+    assert_eq!(body["status"], "ok");
+    let checks: Vec<&str> = body["checks"].as_array().unwrap().iter().map(|c| c["name"].as_str().unwrap()).collect();
+    assert_eq!(checks, ["static_dir", "logging", "game_state"]);
+
+    // Probes stay out of the access log; other requests are still logged.
+    let response = app.clone().oneshot(request("/api/game-of-life/state?probe=health-other", "health-other")).await.unwrap();
+    to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert!(access_log_line("probe=health-other").is_some());
+    assert!(access_log_line("probe=health-live").is_none());
+    assert!(access_log_line("probe=health-ready").is_none());
+
+    // A held game lock makes the server unready.
+    let (locked, unlock) = (std::sync::mpsc::channel(), std::sync::mpsc::channel::<()>());
+    let holder = {
+        let game_state = game_state.clone();
+        std::thread::spawn(move || {
+            let _guard = game_state.lock().unwrap();
+            locked.0.send(()).unwrap();
+            unlock.1.recv().unwrap();
+        })
+    };
+    locked.1.recv().unwrap();
+    let response = app.clone().oneshot(request("/readyz", "health-locked")).await.unwrap();
+    unlock.0.send(()).unwrap();
+    holder.join().unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body = json(response).await;
+    assert_eq!(body["status"], "unavailable");
+    let game_check = &body["checks"][2];
+    assert_eq!(game_check["ok"], false);
+    assert!(game_check["error"].as_str().unwrap().contains("within 20ms"), "{}", body);
+
+    // As does a missing static directory.
+    let response = build(&root.join("missing")).oneshot(request("/readyz", "health-missing")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body = json(response).await;
+    assert_eq!(body["checks"][0]["ok"], false);
+    assert_eq!(body["checks"][1]["ok"], true);
+
+    // And a missing log file, which the check must not recreate.
+    std::fs::remove_file(root.join("app.log")).unwrap();
+    let response = app.clone().oneshot(request("/readyz", "health-no-log")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body = json(response).await;
+    assert_eq!(body["checks"][1]["ok"], false);
+    assert!(!root.join("app.log").exists());
+
+    // The server checks the files its log4rs config writes.
+    let config = std::path::Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../log4rs.yaml"));
+    let files = backend::health::appender_paths(config).unwrap();
+    assert!(files.contains(&"logs/app.log".into()), "{:?}", files);
+    assert!(files.contains(&"logs/access.log".into()), "{:?}", files);
+    assert!(backend::health::appender_paths(&root.join("missing.yaml")).is_err());
+}
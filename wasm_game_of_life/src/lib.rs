@@ -6,12 +6,21 @@ use game_of_life::engines::{UltimateEngine, GameOfLifeEngine};
 use game_of_life::grid::{StandardGrid, Grid};
 use text_to_input::text_to_pixel_art;
 use log::info;
-use gif::{Encoder, Frame, Repeat};
+use gif::{DisposalMethod, Encoder, Frame, Repeat};
+use std::borrow::Cow;
 
 // Default grid dimensions for backward compatibility
 const DEFAULT_WIDTH: usize = 20;
 const DEFAULT_HEIGHT: usize = 20;
 
+// Fixed palette indices used for grid frames
+const DEAD_INDEX: u8 = 0;
+const ALIVE_INDEX: u8 = 1;
+const TRANSPARENT_INDEX: u8 = 2;
+
+// Largest image buffer built in one piece, in bytes
+const MAX_IMAGE_BYTES: usize = 256 * 1024 * 1024;
+
 #[wasm_bindgen]
 extern "C" {
     fn alert(s: &str);
@@ -23,12 +32,176 @@ pub fn init_logging() {
     info!("WASM logging initialized with UltimateEngine");
 }
 
+/// Edge behavior for the wrapper's own stepping
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Topology {
+    /// Cells beyond the edges are always dead
+    Bounded,
+    /// Edges wrap around to the opposite side
+    Torus,
+}
+
+impl Topology {
+    fn parse(name: &str) -> Option<Topology> {
+        match name.to_ascii_lowercase().as_str() {
+            "bounded" => Some(Topology::Bounded),
+            "torus" => Some(Topology::Torus),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Topology::Bounded => "bounded",
+            Topology::Torus => "torus",
+        }
+    }
+}
+
+/// Find the engine's edge behavior by stepping a blinker laid across the
+/// left/right seam of a small board: it only survives if the edges wrap
+fn engine_topology() -> Topology {
+    let mut grid = StandardGrid::new(5, 5);
+    for col in [4, 0, 1] {
+        grid.set_cell(2, col, true);
+    }
+    let mut engine = UltimateEngine::<4>::new(5, 5);
+    engine.set_grid(&grid);
+    engine.step();
+    if engine.get_cell(1, 0) && engine.get_cell(3, 0) {
+        Topology::Torus
+    } else {
+        Topology::Bounded
+    }
+}
+
+/// Life-like birth/survival rule, indexed by live neighbor count
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Rule {
+    birth: [bool; 9],
+    survival: [bool; 9],
+}
+
+impl Rule {
+    fn conway() -> Rule {
+        Rule::parse("B3/S23").unwrap()
+    }
+
+    /// Parse `B3/S23` notation (either order, any case) or the older
+    /// survival/birth form `23/3`
+    fn parse(notation: &str) -> Result<Rule, String> {
+        let invalid = || format!("Invalid rule: {} (expected e.g. B3/S23)", notation);
+        let (first, second) = notation.trim().split_once('/').ok_or_else(invalid)?;
+        let counts = |digits: &str| -> Result<[bool; 9], String> {
+            let mut counts = [false; 9];
+            for ch in digits.chars() {
+                let n = ch.to_digit(10).filter(|&n| n <= 8).ok_or_else(invalid)?;
+                counts[n as usize] = true;
+            }
+            Ok(counts)
+        };
+
+        fn tagged(part: &str, tag: char) -> Option<&str> {
+            part.strip_prefix(tag).or_else(|| part.strip_prefix(tag.to_ascii_lowercase()))
+        }
+        let (birth, survival) = match (tagged(first, 'B'), tagged(second, 'S'), tagged(first, 'S'), tagged(second, 'B')) {
+            (Some(b), Some(s), _, _) | (_, _, Some(s), Some(b)) => (b, s),
+            _ => (second, first),
+        };
+
+        Ok(Rule {
+            birth: counts(birth)?,
+            survival: counts(survival)?,
+        })
+    }
+
+    fn notation(&self) -> String {
+        let digits = |counts: &[bool; 9]| -> String {
+            (0..9).filter(|&n| counts[n]).map(|n| char::from(b'0' + n as u8)).collect()
+        };
+        format!("B{}/S{}", digits(&self.birth), digits(&self.survival))
+    }
+}
+
+/// When `step_until` stops advancing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StopCondition {
+    /// No live cells remain
+    Extinction,
+    /// A step left the board unchanged
+    Stable,
+    /// Population is at least this many cells
+    PopulationAtLeast(usize),
+    /// Population is at most this many cells
+    PopulationAtMost(usize),
+}
+
+impl StopCondition {
+    /// Parse `extinction`, `stable`, `population>=N` or `population<=N`
+    /// (`>` and `<` are accepted as strict thresholds)
+    fn parse(condition: &str) -> Result<StopCondition, String> {
+        let condition = condition.trim().to_ascii_lowercase();
+        let invalid = || {
+            format!(
+                "Unknown condition: {} (expected extinction, stable, population>=N or population<=N)",
+                condition
+            )
+        };
+        match condition.as_str() {
+            "extinction" => return Ok(StopCondition::Extinction),
+            "stable" => return Ok(StopCondition::Stable),
+            _ => {}
+        }
+
+        let threshold = condition.strip_prefix("population").ok_or_else(invalid)?.trim_start();
+        let number = |digits: &str| digits.trim().parse::<usize>().map_err(|_| invalid());
+        if let Some(n) = threshold.strip_prefix(">=") {
+            Ok(StopCondition::PopulationAtLeast(number(n)?))
+        } else if let Some(n) = threshold.strip_prefix("<=") {
+            Ok(StopCondition::PopulationAtMost(number(n)?))
+        } else if let Some(n) = threshold.strip_prefix('>') {
+            number(n)?
+                .checked_add(1)
+                .map(StopCondition::PopulationAtLeast)
+                .ok_or_else(invalid)
+        } else if let Some(n) = threshold.strip_prefix('<') {
+            number(n)?
+                .checked_sub(1)
+                .map(StopCondition::PopulationAtMost)
+                .ok_or_else(invalid)
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
 /// Game of Life WASM wrapper that maintains compatibility with existing interface
 #[wasm_bindgen]
 pub struct GameOfLifeWasm {
     engine: UltimateEngine<4>,
     width: usize,
     height: usize,
+    // Flat copy of the engine's cells, 1 for alive, kept in sync for zero-copy reads
+    cells: Vec<u8>,
+    // Indices of cells flipped by the most recent step
+    changed: Vec<u32>,
+    // Set when cells has edits not yet pushed to the engine
+    dirty: bool,
+    // Generations each cell has been alive, 0 for dead cells
+    ages: Vec<u16>,
+    // Reusable RGBA output of render_rgba
+    pixels: Vec<u8>,
+    render_width: usize,
+    render_height: usize,
+    // Generations over which live cells fade; 0 disables age coloring
+    fade_generations: u16,
+    rule: Rule,
+    topology: Topology,
+    // Edge behavior of the engine itself, found by probing it
+    engine_topology: Topology,
+    // Set while the rule or topology differs from what the engine runs;
+    // steps then run in the wrapper instead of the engine
+    custom_step: bool,
 }
 
 #[wasm_bindgen]
@@ -36,52 +209,161 @@ impl GameOfLifeWasm {
     #[wasm_bindgen(constructor)]
     pub fn new(width: usize, height: usize) -> GameOfLifeWasm {
         let engine = UltimateEngine::new(width, height);
+        let topology = engine_topology();
         info!("Created new GameOfLifeWasm with dimensions {}x{}", width, height);
         GameOfLifeWasm {
             engine,
             width,
             height,
+            cells: vec![0; width * height],
+            changed: Vec::new(),
+            dirty: false,
+            ages: vec![0; width * height],
+            pixels: Vec::new(),
+            render_width: 0,
+            render_height: 0,
+            fade_generations: 0,
+            rule: Rule::conway(),
+            topology,
+            engine_topology: topology,
+            custom_step: false,
         }
     }
 
     /// Get the current grid state as a flat array
     #[wasm_bindgen]
     pub fn get_state(&self) -> Vec<u8> {
-        let mut state = vec![0u8; self.width * self.height];
-        for row in 0..self.height {
-            for col in 0..self.width {
-                let idx = row * self.width + col;
-                state[idx] = if self.engine.get_cell(row, col) { 1 } else { 0 };
-            }
-        }
-        state
+        self.cells.clone()
+    }
+
+    /// Pointer to the flat cell buffer, for viewing the state as a
+    /// `Uint8Array` over WASM memory without copying. The view is invalidated
+    /// when the grid is resized or WASM memory grows.
+    #[wasm_bindgen]
+    pub fn state_ptr(&self) -> *const u8 {
+        self.cells.as_ptr()
+    }
+
+    /// Length of the flat cell buffer
+    #[wasm_bindgen]
+    pub fn state_len(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Get the indices of cells that flipped during the last `step`, or
+    /// across the whole of the last `step_n`/`step_until` call
+    #[wasm_bindgen]
+    pub fn changed_cells(&self) -> Vec<u32> {
+        self.changed.clone()
     }
 
     /// Step the simulation forward one generation
     #[wasm_bindgen]
     pub fn step(&mut self) {
+        if self.custom_step {
+            self.step_custom();
+            return;
+        }
+        self.flush_edits();
         self.engine.step();
+        self.sync_from_engine(true);
+    }
+
+    /// Step forward `n` generations in a single call. Returns the number of
+    /// generations run.
+    #[wasm_bindgen]
+    pub fn step_n(&mut self, n: u32) -> u32 {
+        let start = self.cells.clone();
+        for _ in 0..n {
+            self.step();
+        }
+        self.changed_since(&start);
+        n
+    }
+
+    /// Step forward until `condition` holds or `max` generations have run.
+    /// Conditions are `extinction`, `stable` (a step changes nothing) and
+    /// population thresholds such as `population>=100` or `population<=5`.
+    /// Returns the number of generations run, 0 if the condition already
+    /// held.
+    #[wasm_bindgen]
+    pub fn step_until(&mut self, condition: &str, max: u32) -> Result<u32, JsValue> {
+        let condition = StopCondition::parse(condition).map_err(|e| JsValue::from_str(&e))?;
+        let start = self.cells.clone();
+        let mut generations = 0;
+        while generations < max {
+            let population = self.count_live_cells();
+            let done = match condition {
+                StopCondition::Extinction => population == 0,
+                StopCondition::PopulationAtLeast(n) => population >= n,
+                StopCondition::PopulationAtMost(n) => population <= n,
+                // Checked after stepping, since it depends on the step itself
+                StopCondition::Stable => false,
+            };
+            if done {
+                break;
+            }
+            self.step();
+            generations += 1;
+            if condition == StopCondition::Stable && self.changed.is_empty() {
+                break;
+            }
+        }
+        self.changed_since(&start);
+        Ok(generations)
+    }
+
+    /// Set the birth/survival rule, e.g. `"B36/S23"` for HighLife
+    #[wasm_bindgen]
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), JsValue> {
+        self.rule = Rule::parse(rule).map_err(|e| JsValue::from_str(&e))?;
+        self.update_stepper();
+        info!("Using rule {}", self.rule.notation());
+        Ok(())
+    }
+
+    /// Get the current rule in `B3/S23` notation
+    #[wasm_bindgen]
+    pub fn get_rule(&self) -> String {
+        self.rule.notation()
+    }
+
+    /// Set edge behavior: `"bounded"` (dead beyond the edges) or `"torus"`
+    /// (edges wrap around)
+    #[wasm_bindgen]
+    pub fn set_topology(&mut self, topology: &str) -> Result<(), JsValue> {
+        self.topology = Topology::parse(topology).ok_or_else(|| {
+            JsValue::from_str(&format!("Unknown topology: {} (expected bounded or torus)", topology))
+        })?;
+        self.update_stepper();
+        info!("Using {} topology", self.topology.name());
+        Ok(())
+    }
+
+    /// Get the current topology name
+    #[wasm_bindgen]
+    pub fn get_topology(&self) -> String {
+        self.topology.name().to_string()
     }
 
-    /// Set a cell at the given row and column
+    /// Set a cell at the given row and column. Edits are buffered and pushed
+    /// to the engine in one pass before the next step.
     #[wasm_bindgen]
     pub fn set_cell(&mut self, row: usize, col: usize, alive: bool) {
         if row < self.height && col < self.width {
-            // Create a new grid with the updated cell
-            let mut grid = StandardGrid::new(self.width, self.height);
-            
-            // Copy current state
-            for r in 0..self.height {
-                for c in 0..self.width {
-                    grid.set_cell(r, c, self.engine.get_cell(r, c));
-                }
+            self.edit_cell(row * self.width + col, alive);
+        }
+    }
+
+    /// Set every cell in `indices` (flat `row * width + col` indices) to
+    /// `alive`, e.g. for a brush stroke. Out-of-range indices are ignored.
+    #[wasm_bindgen]
+    pub fn set_cells(&mut self, indices: &[u32], alive: bool) {
+        for &idx in indices {
+            let idx = idx as usize;
+            if idx < self.cells.len() {
+                self.edit_cell(idx, alive);
             }
-            
-            // Set the new cell
-            grid.set_cell(row, col, alive);
-            
-            // Update the engine
-            self.engine.set_grid(&grid);
         }
     }
 
@@ -89,7 +371,7 @@ impl GameOfLifeWasm {
     #[wasm_bindgen]
     pub fn get_cell(&self, row: usize, col: usize) -> bool {
         if row < self.height && col < self.width {
-            self.engine.get_cell(row, col)
+            self.cells[row * self.width + col] != 0
         } else {
             false
         }
@@ -105,19 +387,69 @@ impl GameOfLifeWasm {
     /// Clear the grid
     #[wasm_bindgen]
     pub fn clear(&mut self) {
-        let grid = StandardGrid::new(self.width, self.height);
-        self.engine.set_grid(&grid);
+        self.cells.fill(0);
+        self.changed.clear();
+        self.ages.fill(0);
+        self.dirty = true;
     }
 
-    /// Resize the grid to new dimensions
+    /// Resize the grid to new dimensions, keeping existing cells anchored to
+    /// the top-left corner
     #[wasm_bindgen]
     pub fn resize(&mut self, width: usize, height: usize) {
-        self.engine = UltimateEngine::new(width, height);
-        self.width = width;
-        self.height = height;
+        self.relayout(width, height, 0, 0);
         info!("Resized grid to {}x{}", width, height);
     }
 
+    /// Resize the grid keeping existing cells anchored at `"top-left"` or
+    /// `"center"`. Growing pads with dead cells; shrinking crops whatever
+    /// falls outside the new bounds.
+    #[wasm_bindgen]
+    pub fn resize_anchored(&mut self, width: usize, height: usize, anchor: &str) -> Result<(), JsValue> {
+        let (col_offset, row_offset) = match anchor {
+            "top-left" => (0, 0),
+            "center" => (
+                (width as isize - self.width as isize) / 2,
+                (height as isize - self.height as isize) / 2,
+            ),
+            _ => return Err(JsValue::from_str(&format!("Unknown anchor: {} (expected top-left or center)", anchor))),
+        };
+        self.relayout(width, height, col_offset, row_offset);
+        info!("Resized grid to {}x{} anchored {}", width, height, anchor);
+        Ok(())
+    }
+
+    /// Crop the grid to the bounding box of its live cells plus `margin` dead
+    /// cells on every side. Returns false, leaving the grid unchanged, if
+    /// there are no live cells or the margin makes the grid too large.
+    #[wasm_bindgen]
+    pub fn auto_fit(&mut self, margin: usize) -> bool {
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+        for (idx, _) in self.cells.iter().enumerate().filter(|(_, cell)| **cell != 0) {
+            let (row, col) = (idx / self.width, idx % self.width);
+            bounds = Some(match bounds {
+                None => (col, row, col, row),
+                Some((left, top, right, bottom)) => (left.min(col), top.min(row), right.max(col), bottom.max(row)),
+            });
+        }
+        let Some((left, top, right, bottom)) = bounds else {
+            return false;
+        };
+
+        let padded = |span: usize| margin.checked_mul(2)?.checked_add(span + 1);
+        let (Some(width), Some(height), Ok(offset)) =
+            (padded(right - left), padded(bottom - top), isize::try_from(margin))
+        else {
+            return false;
+        };
+        if width.checked_mul(height).is_none() {
+            return false;
+        }
+        self.relayout(width, height, offset - left as isize, offset - top as isize);
+        info!("Auto-fit grid to {}x{} with margin {}", width, height, margin);
+        true
+    }
+
     /// Load grid from a flat array
     #[wasm_bindgen]
     pub fn load_from_array(&mut self, data: &[u8], width: usize, height: usize) -> Result<(), JsValue> {
@@ -140,6 +472,9 @@ impl GameOfLifeWasm {
         }
 
         self.engine.set_grid(&grid);
+        self.dirty = false;
+        self.ages.fill(0);
+        self.sync_from_engine(false);
         info!("Loaded grid from array with dimensions {}x{}", width, height);
         Ok(())
     }
@@ -156,19 +491,292 @@ impl GameOfLifeWasm {
     /// Count live cells
     #[wasm_bindgen]
     pub fn count_live_cells(&self) -> usize {
-        self.engine.count_live_cells()
+        self.cells.iter().filter(|&&cell| cell != 0).count()
+    }
+
+    /// Fade live cells toward the dead color over `fade_generations`
+    /// generations of survival; 0 draws every live cell in the alive color
+    #[wasm_bindgen]
+    pub fn set_age_fade(&mut self, fade_generations: u16) {
+        self.fade_generations = fade_generations;
+    }
+
+    /// Get how many generations the cell at the given row and column has
+    /// been alive, or 0 if it is dead
+    #[wasm_bindgen]
+    pub fn get_age(&self, row: usize, col: usize) -> u16 {
+        if row < self.height && col < self.width {
+            self.ages[row * self.width + col]
+        } else {
+            0
+        }
+    }
+
+    /// Draw the grid into the RGBA pixel buffer, `cell_size` pixels per cell.
+    /// Colors are `0xRRGGBB`. Grid lines add a one pixel border on the right
+    /// and bottom; use `render_width`/`render_height` for the canvas size and
+    /// `pixels_ptr`/`pixels_len` to view the buffer as `ImageData`.
+    #[wasm_bindgen]
+    pub fn render_rgba(&mut self, cell_size: usize, alive_color: u32, dead_color: u32, grid_lines: bool) -> Result<(), JsValue> {
+        let (render_width, render_height) = self.render_size(cell_size, grid_lines)
+            .map_err(|e| JsValue::from_str(&e))?;
+        self.render_width = render_width;
+        self.render_height = render_height;
+        self.pixels.resize(self.render_width * self.render_height * 4, 0);
+
+        let alive = rgb(alive_color);
+        let dead = rgb(dead_color);
+        // Grid lines sit a quarter of the way from dead toward alive
+        let grid = blend(dead, alive, 0.25);
+
+        // Color each cell once, then fill pixel rows from it
+        let cell_colors: Vec<[u8; 3]> = self.ages.iter()
+            .map(|&age| match age {
+                0 => dead,
+                _ if self.fade_generations == 0 => alive,
+                age => {
+                    let faded = (age - 1).min(self.fade_generations) as f32 / self.fade_generations as f32;
+                    blend(alive, dead, faded * 0.75)
+                }
+            })
+            .collect();
+
+        let render_width = self.render_width;
+        for (y, row) in self.pixels.chunks_mut(render_width * 4).enumerate() {
+            let grid_row = y / cell_size;
+            for (x, pixel) in row.chunks_mut(4).enumerate() {
+                let grid_col = x / cell_size;
+                let color = if grid_lines && (x % cell_size == 0 || y % cell_size == 0) {
+                    grid
+                } else if grid_row < self.height && grid_col < self.width {
+                    cell_colors[grid_row * self.width + grid_col]
+                } else {
+                    dead
+                };
+                pixel.copy_from_slice(&[color[0], color[1], color[2], 255]);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Pointer to the RGBA buffer filled by `render_rgba`
+    #[wasm_bindgen]
+    pub fn pixels_ptr(&self) -> *const u8 {
+        self.pixels.as_ptr()
+    }
+
+    /// Length in bytes of the RGBA buffer filled by `render_rgba`
+    #[wasm_bindgen]
+    pub fn pixels_len(&self) -> usize {
+        self.pixels.len()
+    }
+
+    /// Width in pixels of the last `render_rgba` output
+    #[wasm_bindgen]
+    pub fn render_width(&self) -> usize {
+        self.render_width
+    }
+
+    /// Height in pixels of the last `render_rgba` output
+    #[wasm_bindgen]
+    pub fn render_height(&self) -> usize {
+        self.render_height
     }
 }
 
+impl GameOfLifeWasm {
+    /// Step with the engine whenever it implements the configured rule and
+    /// topology, and in the wrapper otherwise
+    fn update_stepper(&mut self) {
+        self.custom_step = self.rule != Rule::conway() || self.topology != self.engine_topology;
+    }
+
+    /// Pixel size of a `render_rgba` output, checking its buffer stays under
+    /// `MAX_IMAGE_BYTES`
+    fn render_size(&self, cell_size: usize, grid_lines: bool) -> Result<(usize, usize), String> {
+        if cell_size == 0 {
+            return Err("Cell size must be positive".to_string());
+        }
+        let border = grid_lines as usize;
+        let side = |cells: usize| cells.checked_mul(cell_size)?.checked_add(border);
+        let size = side(self.width)
+            .zip(side(self.height))
+            .filter(|&(width, height)| {
+                width.checked_mul(height).and_then(|pixels| pixels.checked_mul(4)).is_some_and(|bytes| bytes <= MAX_IMAGE_BYTES)
+            });
+        size.ok_or_else(|| format!(
+            "Rendering {}x{} cells at cell size {} exceeds {} bytes",
+            self.width, self.height, cell_size, MAX_IMAGE_BYTES
+        ))
+    }
+
+    /// Record an edit in the cell buffer for the next flush
+    fn edit_cell(&mut self, idx: usize, alive: bool) {
+        self.cells[idx] = alive as u8;
+        self.ages[idx] = if alive { self.ages[idx].max(1) } else { 0 };
+        self.dirty = true;
+    }
+
+    /// Move to a `width`x`height` grid, copying each cell shifted by the given
+    /// offsets and dropping cells that land outside
+    fn relayout(&mut self, width: usize, height: usize, col_offset: isize, row_offset: isize) {
+        let mut cells = vec![0u8; width * height];
+        let mut ages = vec![0u16; width * height];
+        for (idx, &cell) in self.cells.iter().enumerate().filter(|(_, cell)| **cell != 0) {
+            let row = (idx / self.width) as isize + row_offset;
+            let col = (idx % self.width) as isize + col_offset;
+            if row >= 0 && col >= 0 && (row as usize) < height && (col as usize) < width {
+                let target = row as usize * width + col as usize;
+                cells[target] = cell;
+                ages[target] = self.ages[idx];
+            }
+        }
+
+        self.engine = UltimateEngine::new(width, height);
+        self.width = width;
+        self.height = height;
+        self.cells = cells;
+        self.ages = ages;
+        self.changed.clear();
+        self.dirty = true;
+    }
+
+    /// Push buffered edits to the engine
+    fn flush_edits(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        let mut grid = StandardGrid::new(self.width, self.height);
+        for (idx, &cell) in self.cells.iter().enumerate() {
+            if cell != 0 {
+                grid.set_cell(idx / self.width, idx % self.width, true);
+            }
+        }
+        self.engine.set_grid(&grid);
+        self.dirty = false;
+    }
+
+    /// Replace `changed` with the cells that differ from `start`
+    fn changed_since(&mut self, start: &[u8]) {
+        self.changed = (0..self.cells.len() as u32)
+            .filter(|&idx| self.cells[idx as usize] != start[idx as usize])
+            .collect();
+    }
+
+    /// Advance one generation in the wrapper using the configured rule and
+    /// topology
+    fn step_custom(&mut self) {
+        let (width, height) = (self.width as isize, self.height as isize);
+        let torus = self.topology == Topology::Torus;
+        let alive_at = |row: isize, col: isize| -> usize {
+            let (row, col) = if torus {
+                (row.rem_euclid(height), col.rem_euclid(width))
+            } else if row < 0 || col < 0 || row >= height || col >= width {
+                return 0;
+            } else {
+                (row, col)
+            };
+            (self.cells[(row * width + col) as usize] != 0) as usize
+        };
+
+        let mut next = vec![0u8; self.cells.len()];
+        for row in 0..height {
+            for col in 0..width {
+                let mut neighbors = 0;
+                for dr in -1..=1 {
+                    for dc in -1..=1 {
+                        if dr != 0 || dc != 0 {
+                            neighbors += alive_at(row + dr, col + dc);
+                        }
+                    }
+                }
+                let idx = (row * width + col) as usize;
+                let alive = if self.cells[idx] != 0 {
+                    self.rule.survival[neighbors]
+                } else {
+                    self.rule.birth[neighbors]
+                };
+                next[idx] = alive as u8;
+            }
+        }
+
+        self.changed.clear();
+        for (idx, &cell) in next.iter().enumerate() {
+            if cell != self.cells[idx] {
+                self.changed.push(idx as u32);
+            }
+            self.ages[idx] = if cell != 0 { self.ages[idx].saturating_add(1) } else { 0 };
+        }
+        self.cells = next;
+        // The engine no longer matches; resync it if it is ever used again
+        self.dirty = true;
+    }
+
+    /// Refresh the cell buffer and ages from the engine. After a step
+    /// (`stepped`) surviving cells age by a generation and flipped cells are
+    /// recorded; otherwise only births and deaths are applied.
+    fn sync_from_engine(&mut self, stepped: bool) {
+        if stepped {
+            self.changed.clear();
+        }
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let idx = row * self.width + col;
+                let alive = self.engine.get_cell(row, col);
+                if stepped && alive != (self.cells[idx] != 0) {
+                    self.changed.push(idx as u32);
+                }
+                self.cells[idx] = alive as u8;
+                self.ages[idx] = match (alive, stepped) {
+                    (false, _) => 0,
+                    (true, true) => self.ages[idx].saturating_add(1),
+                    (true, false) => self.ages[idx].max(1),
+                };
+            }
+        }
+    }
+}
+
+/// Split a `0xRRGGBB` color into channels
+fn rgb(color: u32) -> [u8; 3] {
+    [(color >> 16) as u8, (color >> 8) as u8, color as u8]
+}
+
+/// Linear blend from `from` toward `to` by `t` in `0.0..=1.0`
+fn blend(from: [u8; 3], to: [u8; 3], t: f32) -> [u8; 3] {
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    [mix(from[0], to[0]), mix(from[1], to[1]), mix(from[2], to[2])]
+}
+
 /// GIF Recorder for capturing Game of Life animations
+///
+/// Frames are encoded into the output as they are captured, so memory use
+/// stays flat no matter how long the recording runs.
 #[wasm_bindgen]
 pub struct GifRecorder {
-    buffer: Vec<u8>,
     frame_delay_ms: u16,
     width: u16,
     height: u16,
     is_recording: bool,
-    frames: Vec<Vec<u8>>,
+    encoder: Option<Encoder<Vec<u8>>>,
+    frames_captured: usize,
+    max_frames: usize,
+    max_bytes: usize,
+    // Fixed palette for grid frames; None while recording RGB frames
+    palette: Option<[u8; 6]>,
+    // Last grid frame, to diff the next one against
+    previous_grid_frame: Option<Vec<u8>>,
+    // Grid frame held back so that following unchanged frames can extend its delay
+    pending_frame: Option<Frame<'static>>,
+}
+
+#[derive(Serialize)]
+struct RecordingProgress {
+    frames: usize,
+    bytes: usize,
+    max_frames: usize,
+    max_bytes: usize,
 }
 
 #[wasm_bindgen]
@@ -176,12 +784,17 @@ impl GifRecorder {
     #[wasm_bindgen(constructor)]
     pub fn new() -> GifRecorder {
         GifRecorder {
-            buffer: Vec::new(),
             frame_delay_ms: 100,
             width: 0,
             height: 0,
             is_recording: false,
-            frames: Vec::new(),
+            encoder: None,
+            frames_captured: 0,
+            max_frames: 0,
+            max_bytes: 0,
+            palette: None,
+            previous_grid_frame: None,
+            pending_frame: None,
         }
     }
 
@@ -192,17 +805,46 @@ impl GifRecorder {
             return Err(JsValue::from_str("Already recording"));
         }
 
+        if width == 0 || height == 0 || width > u16::MAX as usize || height > u16::MAX as usize {
+            return Err(JsValue::from_str(&format!("Invalid GIF dimensions {}x{}", width, height)));
+        }
+
         self.width = width as u16;
         self.height = height as u16;
         self.frame_delay_ms = frame_delay_ms;
-        self.buffer = Vec::new();
-        self.frames = Vec::new();
+        self.encoder = None;
+        self.frames_captured = 0;
+        self.palette = None;
+        self.previous_grid_frame = None;
+        self.pending_frame = None;
         self.is_recording = true;
 
         info!("Started GIF recording: {}x{} at {}ms per frame", width, height, frame_delay_ms);
         Ok(())
     }
 
+    /// Limit how many frames and output bytes a recording may reach; 0
+    /// disables a limit. Frames captured past a limit are rejected with an
+    /// error, and the recording so far can still be stopped and saved.
+    #[wasm_bindgen]
+    pub fn set_limits(&mut self, max_frames: usize, max_bytes: usize) {
+        self.max_frames = max_frames;
+        self.max_bytes = max_bytes;
+    }
+
+    /// Get recording progress as `{ frames, bytes, max_frames, max_bytes }`
+    #[wasm_bindgen]
+    pub fn get_progress(&self) -> Result<JsValue, JsValue> {
+        let progress = RecordingProgress {
+            frames: self.frames_captured,
+            bytes: self.bytes_written(),
+            max_frames: self.max_frames,
+            max_bytes: self.max_bytes,
+        };
+        serde_wasm_bindgen::to_value(&progress)
+            .map_err(|e| JsValue::from_str(&format!("Serialization failed: {}", e)))
+    }
+
     /// Add a frame to the GIF from RGB image data
     #[wasm_bindgen]
     pub fn capture_frame(&mut self, rgb_data: &[u8]) -> Result<(), JsValue> {
@@ -218,53 +860,142 @@ impl GifRecorder {
             )));
         }
 
-        // Store the RGB frame data directly
-        self.frames.push(rgb_data.to_vec());
+        if self.palette.is_some() {
+            return Err(JsValue::from_str("Cannot mix RGB and grid frames in one recording"));
+        }
+
+        self.check_limits()?;
+
+        let mut frame = Frame::from_rgb(self.width, self.height, rgb_data);
+        frame.delay = self.delay_centiseconds();
+        let encoder = self.encoder(&[])?;
+        encoder.write_frame(&frame)
+            .map_err(|e| JsValue::from_str(&format!("Failed to write frame: {}", e)))?;
+        self.frames_captured += 1;
 
         Ok(())
     }
 
-    /// Stop recording and return the GIF data
+    /// Add a frame straight from a flat grid state, drawing each cell as a
+    /// `cell_size` square. `colors` holds the dead then alive RGB triplets and
+    /// must stay the same for the whole recording.
+    ///
+    /// After the first frame only the changed rectangle is written, with
+    /// unchanged pixels left transparent; frames with no changes extend the
+    /// previous frame's delay.
     #[wasm_bindgen]
-    pub fn stop_recording(&mut self) -> Result<Vec<u8>, JsValue> {
+    pub fn capture_grid_frame(&mut self, state: &[u8], cell_size: usize, colors: &[u8]) -> Result<(), JsValue> {
         if !self.is_recording {
             return Err(JsValue::from_str("Not currently recording"));
         }
 
-        if self.frames.is_empty() {
-            return Err(JsValue::from_str("No frames captured"));
+        if self.encoder.is_some() && self.palette.is_none() {
+            return Err(JsValue::from_str("Cannot mix RGB and grid frames in one recording"));
         }
 
-        // Create the GIF from all collected frames
-        self.buffer.clear();
-        let mut encoder = Encoder::new(&mut self.buffer, self.width, self.height, &[])
-            .map_err(|e| JsValue::from_str(&format!("Failed to create GIF encoder: {}", e)))?;
-        
-        encoder.set_repeat(Repeat::Infinite)
-            .map_err(|e| JsValue::from_str(&format!("Failed to set repeat: {}", e)))?;
+        if cell_size == 0 {
+            return Err(JsValue::from_str("Cell size must be positive"));
+        }
 
-        // Convert frame delay from milliseconds to centiseconds
-        let delay_centiseconds = (self.frame_delay_ms + 5) / 10; // Round up
+        let palette: [u8; 6] = colors.try_into().map_err(|_| {
+            JsValue::from_str(&format!("Expected 6 color bytes (dead RGB, alive RGB), got {}", colors.len()))
+        })?;
+        if self.palette.is_some_and(|existing| existing != palette) {
+            return Err(JsValue::from_str("Colors cannot change during a recording"));
+        }
 
-        // Write all frames
-        for frame_pixels in &self.frames {
-            let mut frame = Frame::from_rgb(self.width, self.height, frame_pixels);
-            frame.delay = delay_centiseconds;
-            
-            encoder.write_frame(&frame)
-                .map_err(|e| JsValue::from_str(&format!("Failed to write frame: {}", e)))?;
+        let width = self.width as usize;
+        let height = self.height as usize;
+        let grid_width = width / cell_size;
+        let grid_height = height / cell_size;
+        if state.len() != grid_width * grid_height {
+            return Err(JsValue::from_str(&format!(
+                "Grid size mismatch: {}x{} pixels at cell size {} needs {} cells, got {}",
+                width, height, cell_size, grid_width * grid_height, state.len()
+            )));
         }
 
-        // Finalize the encoder
-        drop(encoder);
+        self.check_limits()?;
+
+        let mut pixels = vec![DEAD_INDEX; width * height];
+        for (y, row) in pixels.chunks_mut(width).enumerate() {
+            let grid_row = y / cell_size;
+            if grid_row >= grid_height {
+                break;
+            }
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let grid_col = x / cell_size;
+                if grid_col < grid_width && state[grid_row * grid_width + grid_col] != 0 {
+                    *pixel = ALIVE_INDEX;
+                }
+            }
+        }
+
+        let delay = self.delay_centiseconds();
+        let next = match &self.previous_grid_frame {
+            None => Frame {
+                width: self.width,
+                height: self.height,
+                buffer: Cow::Owned(pixels.clone()),
+                delay,
+                dispose: DisposalMethod::Keep,
+                ..Frame::default()
+            },
+            Some(previous) => match diff_frame(previous, &pixels, width) {
+                Some(mut frame) => {
+                    frame.delay = delay;
+                    frame
+                }
+                None => {
+                    if let Some(pending) = self.pending_frame.as_mut() {
+                        pending.delay = pending.delay.saturating_add(delay);
+                    }
+                    self.frames_captured += 1;
+                    return Ok(());
+                }
+            },
+        };
+
+        if self.palette.is_none() {
+            self.palette = Some(palette);
+            let [dr, dg, db, ar, ag, ab] = palette;
+            self.encoder(&[dr, dg, db, ar, ag, ab, 0, 0, 0, 0, 0, 0])?;
+        }
+        if let Some(pending) = self.pending_frame.replace(next) {
+            self.write_grid_frame(&pending)?;
+        }
+        self.previous_grid_frame = Some(pixels);
+        self.frames_captured += 1;
+
+        Ok(())
+    }
+
+    /// Stop recording and return the GIF data
+    #[wasm_bindgen]
+    pub fn stop_recording(&mut self) -> Result<Vec<u8>, JsValue> {
+        if !self.is_recording {
+            return Err(JsValue::from_str("Not currently recording"));
+        }
+
+        if self.frames_captured == 0 {
+            return Err(JsValue::from_str("No frames captured"));
+        }
+
+        if let Some(pending) = self.pending_frame.take() {
+            self.write_grid_frame(&pending)?;
+        }
 
         self.is_recording = false;
-        let gif_data = self.buffer.clone();
-        
-        info!("Stopped GIF recording, generated {} bytes from {} frames", gif_data.len(), self.frames.len());
+        self.previous_grid_frame = None;
+        self.palette = None;
+
+        // Finalize the encoder
+        let gif_data = self.encoder.take()
+            .ok_or_else(|| JsValue::from_str("No frames captured"))?
+            .into_inner()
+            .map_err(|e| JsValue::from_str(&format!("Failed to finish GIF: {}", e)))?;
         
-        // Clear frames to free memory
-        self.frames.clear();
+        info!("Stopped GIF recording, generated {} bytes from {} frames", gif_data.len(), self.frames_captured);
         
         Ok(gif_data)
     }
@@ -282,6 +1013,370 @@ impl GifRecorder {
     }
 }
 
+impl GifRecorder {
+    fn delay_centiseconds(&self) -> u16 {
+        delay_centiseconds(self.frame_delay_ms)
+    }
+
+    fn bytes_written(&self) -> usize {
+        self.encoder.as_ref().map_or(0, |encoder| encoder.get_ref().len())
+    }
+
+    fn check_limits(&self) -> Result<(), JsValue> {
+        match self.limit_reached() {
+            Some(message) => Err(JsValue::from_str(&message)),
+            None => Ok(()),
+        }
+    }
+
+    /// Describe the limit the recording has reached, if any
+    fn limit_reached(&self) -> Option<String> {
+        if self.max_frames > 0 && self.frames_captured >= self.max_frames {
+            return Some(format!(
+                "Recording limit reached: {} frames captured (max {})",
+                self.frames_captured, self.max_frames
+            ));
+        }
+        let bytes = self.bytes_written();
+        if self.max_bytes > 0 && bytes >= self.max_bytes {
+            return Some(format!("Recording limit reached: {} bytes written (max {})", bytes, self.max_bytes));
+        }
+        None
+    }
+
+    /// Get the encoder, creating it with `global_palette` on the first frame
+    fn encoder(&mut self, global_palette: &[u8]) -> Result<&mut Encoder<Vec<u8>>, JsValue> {
+        if self.encoder.is_none() {
+            let mut encoder = Encoder::new(Vec::new(), self.width, self.height, global_palette)
+                .map_err(|e| JsValue::from_str(&format!("Failed to create GIF encoder: {}", e)))?;
+            
+            encoder.set_repeat(Repeat::Infinite)
+                .map_err(|e| JsValue::from_str(&format!("Failed to set repeat: {}", e)))?;
+
+            self.encoder = Some(encoder);
+        }
+        Ok(self.encoder.as_mut().unwrap())
+    }
+
+    fn write_grid_frame(&mut self, frame: &Frame<'_>) -> Result<(), JsValue> {
+        let encoder = self.encoder.as_mut()
+            .ok_or_else(|| JsValue::from_str("Recording has no encoder"))?;
+        encoder.write_frame(frame)
+            .map_err(|e| JsValue::from_str(&format!("Failed to write frame: {}", e)))
+    }
+}
+
+/// Convert a frame delay from milliseconds to the centiseconds GIF stores,
+/// rounding up so short delays never become 0
+fn delay_centiseconds(delay_ms: u16) -> u16 {
+    delay_ms.div_ceil(10)
+}
+
+/// Build a frame covering only the rectangle that changed between two
+/// palette-indexed frames, with unchanged pixels transparent. Returns `None`
+/// if nothing changed.
+fn diff_frame(previous: &[u8], current: &[u8], width: usize) -> Option<Frame<'static>> {
+    let (left, top, right, bottom) = changed_bounds(previous, current, width)?;
+
+    let mut buffer = Vec::with_capacity((right - left) * (bottom - top));
+    for y in top..bottom {
+        let row = y * width;
+        for x in left..right {
+            let idx = row + x;
+            buffer.push(if previous[idx] == current[idx] { TRANSPARENT_INDEX } else { current[idx] });
+        }
+    }
+
+    Some(Frame {
+        left: left as u16,
+        top: top as u16,
+        width: (right - left) as u16,
+        height: (bottom - top) as u16,
+        buffer: Cow::Owned(buffer),
+        transparent: Some(TRANSPARENT_INDEX),
+        dispose: DisposalMethod::Keep,
+        ..Frame::default()
+    })
+}
+
+/// Smallest `(left, top, right, bottom)` rectangle, exclusive on the right and
+/// bottom, containing every pixel that differs between two frames
+fn changed_bounds(previous: &[u8], current: &[u8], width: usize) -> Option<(usize, usize, usize, usize)> {
+    let mut bounds: Option<(usize, usize, usize, usize)> = None;
+    for (idx, _) in previous.iter().zip(current).enumerate().filter(|(_, (a, b))| a != b) {
+        let (x, y) = (idx % width, idx / width);
+        bounds = Some(match bounds {
+            None => (x, y, x + 1, y + 1),
+            Some((left, top, right, bottom)) => (left.min(x), top.min(y), right.max(x + 1), bottom.max(y + 1)),
+        });
+    }
+    bounds
+}
+
+/// Exporter for sharing captures as GIF, APNG or a sprite-sheet PNG
+///
+/// Unlike `GifRecorder`, frames are buffered until export because APNG and
+/// sprite sheets need the frame count up front, so set limits with
+/// `set_limits` to bound memory use. Each frame carries its own delay.
+#[wasm_bindgen]
+pub struct AnimationExporter {
+    width: u16,
+    height: u16,
+    frames: Vec<Vec<u8>>,
+    delays_ms: Vec<u16>,
+    max_frames: usize,
+    max_bytes: usize,
+    loop_count: u16,
+    first_hold_ms: u16,
+    last_hold_ms: u16,
+}
+
+#[wasm_bindgen]
+impl AnimationExporter {
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: usize, height: usize) -> Result<AnimationExporter, JsValue> {
+        if width == 0 || height == 0 || width > u16::MAX as usize || height > u16::MAX as usize {
+            return Err(JsValue::from_str(&format!("Invalid animation dimensions {}x{}", width, height)));
+        }
+
+        Ok(AnimationExporter {
+            width: width as u16,
+            height: height as u16,
+            frames: Vec::new(),
+            delays_ms: Vec::new(),
+            max_frames: 0,
+            max_bytes: 0,
+            loop_count: 0,
+            first_hold_ms: 0,
+            last_hold_ms: 0,
+        })
+    }
+
+    /// Add an RGB frame shown for `delay_ms`
+    #[wasm_bindgen]
+    pub fn add_frame(&mut self, rgb_data: &[u8], delay_ms: u16) -> Result<(), JsValue> {
+        let expected_size = (self.width as usize) * (self.height as usize) * 3;
+        if rgb_data.len() != expected_size {
+            return Err(JsValue::from_str(&format!(
+                "RGB data size mismatch: expected {}x{}x3 = {}, got {}",
+                self.width, self.height, expected_size, rgb_data.len()
+            )));
+        }
+
+        if let Some(message) = self.limit_reached() {
+            return Err(JsValue::from_str(&message));
+        }
+
+        self.frames.push(rgb_data.to_vec());
+        self.delays_ms.push(delay_ms);
+        Ok(())
+    }
+
+    /// Limit how many frames and buffered bytes the exporter may hold; 0
+    /// disables a limit. Frames added past a limit are rejected with an
+    /// error, and the frames so far can still be exported.
+    #[wasm_bindgen]
+    pub fn set_limits(&mut self, max_frames: usize, max_bytes: usize) {
+        self.max_frames = max_frames;
+        self.max_bytes = max_bytes;
+    }
+
+    /// Set how many times the animation plays; 0 loops forever
+    #[wasm_bindgen]
+    pub fn set_loop_count(&mut self, loop_count: u16) {
+        self.loop_count = loop_count;
+    }
+
+    /// Hold the first and last frames for extra time, in milliseconds
+    #[wasm_bindgen]
+    pub fn set_hold(&mut self, first_hold_ms: u16, last_hold_ms: u16) {
+        self.first_hold_ms = first_hold_ms;
+        self.last_hold_ms = last_hold_ms;
+    }
+
+    /// Number of frames added so far
+    #[wasm_bindgen]
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Clear all frames, keeping dimensions and settings
+    #[wasm_bindgen]
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.delays_ms.clear();
+    }
+
+    /// Encode the frames as a GIF
+    #[wasm_bindgen]
+    pub fn export_gif(&self) -> Result<Vec<u8>, JsValue> {
+        self.check_frames()?;
+
+        let mut buffer = Vec::new();
+        {
+            let mut encoder = Encoder::new(&mut buffer, self.width, self.height, &[])
+                .map_err(|e| JsValue::from_str(&format!("Failed to create GIF encoder: {}", e)))?;
+
+            // GIF counts repeats after the first play
+            let repeat = match self.loop_count {
+                0 => Repeat::Infinite,
+                plays => Repeat::Finite(plays - 1),
+            };
+            encoder.set_repeat(repeat)
+                .map_err(|e| JsValue::from_str(&format!("Failed to set repeat: {}", e)))?;
+
+            for (idx, frame_pixels) in self.frames.iter().enumerate() {
+                let mut frame = Frame::from_rgb(self.width, self.height, frame_pixels);
+                frame.delay = delay_centiseconds(self.frame_delay_ms(idx));
+                encoder.write_frame(&frame)
+                    .map_err(|e| JsValue::from_str(&format!("Failed to write frame: {}", e)))?;
+            }
+        }
+
+        info!("Exported GIF: {} bytes from {} frames", buffer.len(), self.frames.len());
+        Ok(buffer)
+    }
+
+    /// Encode the frames as an animated PNG
+    #[wasm_bindgen]
+    pub fn export_apng(&self) -> Result<Vec<u8>, JsValue> {
+        self.check_frames()?;
+
+        let mut buffer = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut buffer, self.width as u32, self.height as u32);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder.set_animated(self.frames.len() as u32, self.loop_count as u32)
+                .map_err(|e| JsValue::from_str(&format!("Failed to configure APNG: {}", e)))?;
+
+            let mut writer = encoder.write_header()
+                .map_err(|e| JsValue::from_str(&format!("Failed to write PNG header: {}", e)))?;
+            for (idx, frame_pixels) in self.frames.iter().enumerate() {
+                writer.set_frame_delay(self.frame_delay_ms(idx), 1000)
+                    .map_err(|e| JsValue::from_str(&format!("Failed to set frame delay: {}", e)))?;
+                writer.write_image_data(frame_pixels)
+                    .map_err(|e| JsValue::from_str(&format!("Failed to write frame: {}", e)))?;
+            }
+            writer.finish()
+                .map_err(|e| JsValue::from_str(&format!("Failed to finish APNG: {}", e)))?;
+        }
+
+        info!("Exported APNG: {} bytes from {} frames", buffer.len(), self.frames.len());
+        Ok(buffer)
+    }
+
+    /// Lay the frames out left to right, top to bottom in one PNG with
+    /// `columns` frames per row (0 picks a roughly square sheet)
+    #[wasm_bindgen]
+    pub fn export_sprite_sheet(&self, columns: usize) -> Result<Vec<u8>, JsValue> {
+        self.check_frames()?;
+
+        let count = self.frames.len();
+        let (columns, rows) = self.sprite_sheet_layout(columns).map_err(|e| JsValue::from_str(&e))?;
+        let frame_width = self.width as usize;
+        let frame_height = self.height as usize;
+        let sheet_width = frame_width * columns;
+        let sheet_height = frame_height * rows;
+
+        let mut sheet = vec![0u8; sheet_width * sheet_height * 3];
+        for (idx, frame_pixels) in self.frames.iter().enumerate() {
+            let x = (idx % columns) * frame_width;
+            let y = (idx / columns) * frame_height;
+            for (row, src) in frame_pixels.chunks(frame_width * 3).enumerate() {
+                let start = ((y + row) * sheet_width + x) * 3;
+                sheet[start..start + src.len()].copy_from_slice(src);
+            }
+        }
+
+        let mut buffer = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut buffer, sheet_width as u32, sheet_height as u32);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header()
+                .map_err(|e| JsValue::from_str(&format!("Failed to write PNG header: {}", e)))?;
+            writer.write_image_data(&sheet)
+                .map_err(|e| JsValue::from_str(&format!("Failed to write sprite sheet: {}", e)))?;
+        }
+
+        info!("Exported {}x{} sprite sheet of {} frames", columns, rows, count);
+        Ok(buffer)
+    }
+}
+
+impl AnimationExporter {
+    fn check_frames(&self) -> Result<(), JsValue> {
+        if self.frames.is_empty() {
+            return Err(JsValue::from_str("No frames captured"));
+        }
+        Ok(())
+    }
+
+    /// Bytes of RGB data currently buffered
+    fn bytes_buffered(&self) -> usize {
+        self.frames.len() * self.frame_bytes()
+    }
+
+    fn frame_bytes(&self) -> usize {
+        self.width as usize * self.height as usize * 3
+    }
+
+    /// Describe the limit that adding another frame would exceed, if any
+    fn limit_reached(&self) -> Option<String> {
+        if self.max_frames > 0 && self.frames.len() >= self.max_frames {
+            return Some(format!(
+                "Export limit reached: {} frames added (max {})",
+                self.frames.len(), self.max_frames
+            ));
+        }
+        let bytes = self.bytes_buffered() + self.frame_bytes();
+        if self.max_bytes > 0 && bytes > self.max_bytes {
+            return Some(format!(
+                "Export limit reached: {} more bytes would exceed the maximum of {}",
+                self.frame_bytes(), self.max_bytes
+            ));
+        }
+        None
+    }
+
+    /// Columns and rows of the sprite sheet for `columns` frames per row (0
+    /// picks a roughly square sheet), checking the sheet fits in memory
+    fn sprite_sheet_layout(&self, columns: usize) -> Result<(usize, usize), String> {
+        let count = self.frames.len();
+        let columns = match columns {
+            0 => (1..=count).find(|c| c * c >= count).unwrap_or(1),
+            c => c.min(count),
+        };
+        let rows = count.div_ceil(columns);
+
+        let max_bytes = match self.max_bytes {
+            0 => MAX_IMAGE_BYTES,
+            limit => limit.min(MAX_IMAGE_BYTES),
+        };
+        let bytes = (columns * rows).checked_mul(self.frame_bytes()).filter(|&bytes| bytes <= max_bytes);
+        match bytes {
+            Some(_) => Ok((columns, rows)),
+            None => Err(format!(
+                "Sprite sheet of {}x{} frames at {}x{} is too large (max {} bytes)",
+                columns, rows, self.width, self.height, max_bytes
+            )),
+        }
+    }
+
+    /// Delay of frame `idx` including any first/last frame hold
+    fn frame_delay_ms(&self, idx: usize) -> u16 {
+        let mut delay = self.delays_ms[idx];
+        if idx == 0 {
+            delay = delay.saturating_add(self.first_hold_ms);
+        }
+        if idx == self.frames.len() - 1 {
+            delay = delay.saturating_add(self.last_hold_ms);
+        }
+        delay
+    }
+}
+
 /// Backward compatibility functions that maintain the original interface
 
 /// Legacy tick function for backward compatibility
@@ -520,3 +1615,505 @@ pub fn get_version() -> String {
 pub fn main() {
     console_error_panic_hook::set_once();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_of_identical_frames_is_empty() {
+        let frame = vec![DEAD_INDEX; 12];
+        assert_eq!(changed_bounds(&frame, &frame, 4), None);
+        assert!(diff_frame(&frame, &frame, 4).is_none());
+    }
+
+    #[test]
+    fn diff_of_single_cell_covers_one_pixel() {
+        let previous = vec![DEAD_INDEX; 12];
+        let mut current = previous.clone();
+        current[2 * 4 + 1] = ALIVE_INDEX;
+
+        assert_eq!(changed_bounds(&previous, &current, 4), Some((1, 2, 2, 3)));
+        let frame = diff_frame(&previous, &current, 4).unwrap();
+        assert_eq!((frame.left, frame.top, frame.width, frame.height), (1, 2, 1, 1));
+        assert_eq!(frame.buffer.as_ref(), [ALIVE_INDEX]);
+        assert_eq!(frame.transparent, Some(TRANSPARENT_INDEX));
+    }
+
+    #[test]
+    fn diff_leaves_unchanged_pixels_transparent() {
+        let previous = vec![DEAD_INDEX; 12];
+        let mut current = previous.clone();
+        current[0] = ALIVE_INDEX;
+        current[4 + 2] = ALIVE_INDEX;
+
+        let frame = diff_frame(&previous, &current, 4).unwrap();
+        assert_eq!((frame.left, frame.top, frame.width, frame.height), (0, 0, 3, 2));
+        assert_eq!(
+            frame.buffer.as_ref(),
+            [ALIVE_INDEX, TRANSPARENT_INDEX, TRANSPARENT_INDEX, TRANSPARENT_INDEX, TRANSPARENT_INDEX, ALIVE_INDEX]
+        );
+    }
+
+    #[test]
+    fn diff_of_full_frame_change_covers_whole_frame() {
+        let previous = vec![DEAD_INDEX; 12];
+        let current = vec![ALIVE_INDEX; 12];
+
+        assert_eq!(changed_bounds(&previous, &current, 4), Some((0, 0, 4, 3)));
+        let frame = diff_frame(&previous, &current, 4).unwrap();
+        assert_eq!((frame.left, frame.top, frame.width, frame.height), (0, 0, 4, 3));
+        assert_eq!(frame.buffer.as_ref(), current.as_slice());
+    }
+
+    #[test]
+    fn rule_parses_birth_survival_notation() {
+        let highlife = Rule::parse("B36/S23").unwrap();
+        assert_eq!(highlife.notation(), "B36/S23");
+        assert!(highlife.birth[3] && highlife.birth[6] && !highlife.birth[2]);
+        assert!(highlife.survival[2] && highlife.survival[3] && !highlife.survival[6]);
+
+        assert_eq!(Rule::parse("S23/B3").unwrap(), Rule::conway());
+        assert_eq!(Rule::parse("b3/s23").unwrap(), Rule::conway());
+        assert_eq!(Rule::parse(" B3/S23 ").unwrap(), Rule::conway());
+        // Older survival/birth form
+        assert_eq!(Rule::parse("23/3").unwrap(), Rule::conway());
+        // Empty sets are valid
+        assert_eq!(Rule::parse("B/S").unwrap().notation(), "B/S");
+        assert_eq!(Rule::parse("B1357/S02468").unwrap().notation(), "B1357/S02468");
+    }
+
+    #[test]
+    fn rule_rejects_malformed_notation() {
+        for notation in ["", "B3", "B3/S9", "B3/S2x", "Bx/S23", "B3/S23/S1"] {
+            assert!(Rule::parse(notation).is_err(), "{} should not parse", notation);
+        }
+        assert_eq!(Rule::parse("B3").unwrap_err(), "Invalid rule: B3 (expected e.g. B3/S23)");
+    }
+
+    #[test]
+    fn topology_parses_names() {
+        assert_eq!(Topology::parse("bounded"), Some(Topology::Bounded));
+        assert_eq!(Topology::parse("Torus"), Some(Topology::Torus));
+        assert_eq!(Topology::parse("TORUS"), Some(Topology::Torus));
+        assert_eq!(Topology::parse("sphere"), None);
+        assert_eq!(Topology::parse(""), None);
+        for topology in [Topology::Bounded, Topology::Torus] {
+            assert_eq!(Topology::parse(topology.name()), Some(topology));
+        }
+    }
+
+    #[test]
+    fn stepper_returns_to_engine_when_settings_match_it() {
+        let mut game = GameOfLifeWasm::new(8, 8);
+        assert!(!game.custom_step);
+        let engine_topology = game.get_topology();
+
+        game.set_rule("B36/S23").unwrap();
+        assert!(game.custom_step);
+        game.set_rule("B3/S23").unwrap();
+        assert!(!game.custom_step);
+
+        let other = if engine_topology == "torus" { "bounded" } else { "torus" };
+        game.set_topology(other).unwrap();
+        assert!(game.custom_step);
+        game.set_topology(&engine_topology).unwrap();
+        assert!(!game.custom_step);
+    }
+
+    #[test]
+    fn stop_condition_parses_thresholds() {
+        assert_eq!(StopCondition::parse("extinction"), Ok(StopCondition::Extinction));
+        assert_eq!(StopCondition::parse(" Stable "), Ok(StopCondition::Stable));
+        assert_eq!(StopCondition::parse("population>=100"), Ok(StopCondition::PopulationAtLeast(100)));
+        assert_eq!(StopCondition::parse("population <= 5"), Ok(StopCondition::PopulationAtMost(5)));
+        assert_eq!(StopCondition::parse("Population>9"), Ok(StopCondition::PopulationAtLeast(10)));
+        assert_eq!(StopCondition::parse("population<1"), Ok(StopCondition::PopulationAtMost(0)));
+    }
+
+    #[test]
+    fn stop_condition_rejects_unreachable_and_malformed_thresholds() {
+        for condition in [
+            format!("population>{}", usize::MAX),
+            "population<0".to_string(),
+            "population=5".to_string(),
+            "population>=".to_string(),
+            "population>=-1".to_string(),
+            "crowded".to_string(),
+        ] {
+            assert!(StopCondition::parse(&condition).is_err(), "{} should not parse", condition);
+        }
+    }
+
+    /// A game stepped by the wrapper, so results don't depend on the engine
+    fn custom_game(cells: &[(usize, usize)]) -> GameOfLifeWasm {
+        let mut game = GameOfLifeWasm::new(8, 8);
+        game.set_rule("B36/S23").unwrap();
+        for &(row, col) in cells {
+            game.set_cell(row, col, true);
+        }
+        game
+    }
+
+    const BLINKER: [(usize, usize); 3] = [(3, 2), (3, 3), (3, 4)];
+
+    #[test]
+    fn step_until_stops_when_condition_holds() {
+        let mut game = custom_game(&[(4, 4)]);
+        assert_eq!(game.step_until("extinction", 10).unwrap(), 1);
+        assert_eq!(game.count_live_cells(), 0);
+        // Already holds, so nothing runs
+        assert_eq!(game.step_until("extinction", 10).unwrap(), 0);
+
+        let mut game = custom_game(&[(2, 2), (2, 3), (3, 2), (3, 3)]);
+        assert_eq!(game.step_until("stable", 10).unwrap(), 1);
+        assert!(game.changed_cells().is_empty());
+
+        let mut game = custom_game(&BLINKER);
+        assert_eq!(game.step_until("population>=3", 10).unwrap(), 0);
+        assert_eq!(game.step_until("population<3", 10).unwrap(), 10);
+    }
+
+    #[test]
+    fn step_until_reports_changes_across_the_whole_run() {
+        let mut game = custom_game(&BLINKER);
+        // An oscillator is never stable, so the run ends at `max`
+        assert_eq!(game.step_until("stable", 3).unwrap(), 3);
+        let mut changed = game.changed_cells();
+        changed.sort();
+        assert_eq!(changed, [2 * 8 + 3, 3 * 8 + 2, 3 * 8 + 4, 4 * 8 + 3]);
+
+        assert_eq!(game.step_n(1), 1);
+        assert_eq!(game.changed_cells().len(), 4);
+        assert_eq!(game.step_until("stable", 2).unwrap(), 2);
+        assert!(game.changed_cells().is_empty());
+    }
+
+    #[test]
+    fn set_cells_ignores_out_of_range_indices() {
+        let mut game = GameOfLifeWasm::new(8, 8);
+        game.set_cells(&[0, 63, 64, 1000, u32::MAX], true);
+        assert_eq!(game.count_live_cells(), 2);
+        assert!(game.get_cell(0, 0) && game.get_cell(7, 7));
+
+        // Nothing in range means nothing to flush
+        let mut game = GameOfLifeWasm::new(8, 8);
+        game.set_cells(&[64, u32::MAX], true);
+        game.set_cell(8, 0, true);
+        assert!(!game.dirty);
+        assert_eq!(game.count_live_cells(), 0);
+    }
+
+    #[test]
+    fn repeated_edits_before_a_flush_keep_the_last() {
+        let mut game = GameOfLifeWasm::new(8, 8);
+        game.set_cells(&[9, 9, 10], true);
+        game.set_cell(1, 1, false);
+        game.toggle_cell(1, 2);
+        game.set_cells(&[9], true);
+        assert_eq!(game.count_live_cells(), 1);
+        assert_eq!(game.get_age(1, 1), 1);
+        assert_eq!(game.get_age(1, 2), 0);
+
+        game.flush_edits();
+        assert!(!game.dirty);
+        assert!(game.engine.get_cell(1, 1));
+        assert!(!game.engine.get_cell(1, 2));
+        assert_eq!(game.engine.count_live_cells(), 1);
+    }
+
+    #[test]
+    fn reads_see_pending_edits() {
+        let mut game = GameOfLifeWasm::new(4, 4);
+        game.set_cells(&[5], true);
+        assert!(game.dirty);
+
+        let view = unsafe { std::slice::from_raw_parts(game.state_ptr(), game.state_len()) };
+        assert_eq!(view[5], 1);
+        assert_eq!(game.get_state()[5], 1);
+        game.render_rgba(1, WHITE, 0x000000, false).unwrap();
+        assert_eq!(pixel(&game, 1, 1), [255, 255, 255, 255]);
+
+        // The engine only catches up when the edits are flushed
+        assert!(!game.engine.get_cell(1, 1));
+        game.flush_edits();
+        assert!(game.engine.get_cell(1, 1));
+    }
+
+    fn live_cells(game: &GameOfLifeWasm) -> Vec<(usize, usize)> {
+        (0..game.cells.len())
+            .filter(|&idx| game.cells[idx] != 0)
+            .map(|idx| (idx / game.width, idx % game.width))
+            .collect()
+    }
+
+    #[test]
+    fn resize_anchors_existing_cells() {
+        let mut game = GameOfLifeWasm::new(4, 4);
+        game.set_cells(&[5, 15], true);
+
+        game.resize_anchored(8, 8, "top-left").unwrap();
+        assert_eq!((game.width, game.height), (8, 8));
+        assert_eq!(live_cells(&game), [(1, 1), (3, 3)]);
+
+        let mut game = GameOfLifeWasm::new(4, 4);
+        game.set_cells(&[5, 15], true);
+        game.resize_anchored(8, 8, "center").unwrap();
+        assert_eq!(live_cells(&game), [(3, 3), (5, 5)]);
+
+        // Odd growth puts the extra row and column after the cells
+        let mut game = GameOfLifeWasm::new(4, 4);
+        game.set_cell(0, 0, true);
+        game.resize_anchored(7, 5, "center").unwrap();
+        assert_eq!((game.width, game.height), (7, 5));
+        assert_eq!(live_cells(&game), [(0, 1)]);
+    }
+
+    #[test]
+    fn shrinking_crops_cells_outside_the_new_bounds() {
+        let scattered = || {
+            let mut game = GameOfLifeWasm::new(8, 8);
+            for (row, col) in [(0, 0), (3, 3), (4, 5), (7, 7)] {
+                game.set_cell(row, col, true);
+            }
+            game.ages[3 * 8 + 3] = 5;
+            game
+        };
+
+        let mut game = scattered();
+        game.resize_anchored(4, 4, "top-left").unwrap();
+        assert_eq!(live_cells(&game), [(0, 0), (3, 3)]);
+        assert_eq!(game.get_age(3, 3), 5);
+        assert_eq!(game.ages.len(), 16);
+
+        let mut game = scattered();
+        game.resize_anchored(4, 4, "center").unwrap();
+        assert_eq!(live_cells(&game), [(1, 1), (2, 3)]);
+        assert_eq!(game.get_age(1, 1), 5);
+    }
+
+    #[test]
+    fn auto_fit_crops_to_live_cells_plus_margin() {
+        let mut game = GameOfLifeWasm::new(10, 10);
+        game.set_cell(2, 3, true);
+        game.set_cell(4, 5, true);
+        assert!(game.auto_fit(1));
+        assert_eq!((game.width, game.height), (5, 5));
+        assert_eq!(live_cells(&game), [(1, 1), (3, 3)]);
+
+        assert!(game.auto_fit(0));
+        assert_eq!((game.width, game.height), (3, 3));
+        assert_eq!(live_cells(&game), [(0, 0), (2, 2)]);
+
+        let mut empty = GameOfLifeWasm::new(6, 6);
+        assert!(!empty.auto_fit(2));
+        assert_eq!((empty.width, empty.height), (6, 6));
+    }
+
+    #[test]
+    fn auto_fit_rejects_margins_that_overflow() {
+        let mut game = GameOfLifeWasm::new(6, 6);
+        game.set_cell(2, 2, true);
+        for margin in [usize::MAX, usize::MAX / 2, isize::MAX as usize + 1, usize::MAX / 4] {
+            assert!(!game.auto_fit(margin), "margin {}", margin);
+            assert_eq!((game.width, game.height), (6, 6));
+            assert_eq!(live_cells(&game), [(2, 2)]);
+        }
+    }
+
+    fn pixel(game: &GameOfLifeWasm, x: usize, y: usize) -> [u8; 4] {
+        let start = (y * game.render_width() + x) * 4;
+        game.pixels[start..start + 4].try_into().unwrap()
+    }
+
+    const WHITE: u32 = 0xFFFFFF;
+
+    #[test]
+    fn render_fills_a_buffer_of_the_reported_size() {
+        let mut game = GameOfLifeWasm::new(8, 6);
+        game.render_rgba(3, WHITE, 0, false).unwrap();
+        assert_eq!((game.render_width(), game.render_height()), (24, 18));
+        assert_eq!(game.pixels_len(), 24 * 18 * 4);
+
+        // Grid lines add a border on the right and bottom; the buffer shrinks
+        // again without them.
+        game.render_rgba(3, WHITE, 0, true).unwrap();
+        assert_eq!((game.render_width(), game.render_height()), (25, 19));
+        assert_eq!(game.pixels_len(), 25 * 19 * 4);
+        game.render_rgba(1, WHITE, 0, false).unwrap();
+        assert_eq!(game.pixels_len(), 8 * 6 * 4);
+    }
+
+    #[test]
+    fn render_draws_grid_lines_between_cells() {
+        let mut game = GameOfLifeWasm::new(4, 4);
+        game.set_cell(0, 0, true);
+        game.render_rgba(4, WHITE, 0x000000, true).unwrap();
+
+        let grid = [64, 64, 64, 255];
+        for (x, y) in [(0, 0), (4, 1), (1, 4), (8, 9), (16, 2), (3, 16), (16, 16)] {
+            assert_eq!(pixel(&game, x, y), grid, "({}, {})", x, y);
+        }
+        assert_eq!(pixel(&game, 1, 1), [255, 255, 255, 255]);
+        assert_eq!(pixel(&game, 3, 3), [255, 255, 255, 255]);
+        assert_eq!(pixel(&game, 5, 5), [0, 0, 0, 255]);
+
+        game.render_rgba(4, WHITE, 0x000000, false).unwrap();
+        assert_eq!(pixel(&game, 0, 0), [255, 255, 255, 255]);
+        assert_eq!(pixel(&game, 4, 4), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn render_fades_cells_with_age() {
+        let mut game = custom_game(&[(2, 2), (2, 3), (3, 2), (3, 3)]);
+        game.set_age_fade(2);
+        let mut colors = Vec::new();
+        for _ in 0..4 {
+            game.render_rgba(1, WHITE, 0x000000, false).unwrap();
+            colors.push(pixel(&game, 2, 2));
+            game.step();
+        }
+        // Newborn cells are fully alive, then fade three quarters of the way
+        // to dead over two generations and stay there
+        assert_eq!(colors, [[255, 255, 255, 255], [159, 159, 159, 255], [64, 64, 64, 255], [64, 64, 64, 255]]);
+        assert_eq!(pixel(&game, 0, 0), [0, 0, 0, 255]);
+
+        game.set_age_fade(0);
+        game.render_rgba(1, WHITE, 0x000000, false).unwrap();
+        assert_eq!(pixel(&game, 2, 2), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn render_size_is_bounded() {
+        let game = GameOfLifeWasm::new(8, 8);
+        assert_eq!(game.render_size(2, true), Ok((17, 17)));
+        assert!(game.render_size(0, false).is_err());
+        assert!(game.render_size(usize::MAX / 4, false).is_err());
+        assert!(game.render_size(usize::MAX, true).is_err());
+        // Just over the byte limit
+        let cell_size = ((MAX_IMAGE_BYTES / 4) as f64).sqrt() as usize / 8 + 1;
+        assert!(game.render_size(cell_size, false).unwrap_err().contains("exceeds"));
+        assert!(game.render_size(cell_size - 1, false).is_ok());
+    }
+
+    const COLORS: [u8; 6] = [0, 0, 0, 255, 255, 255];
+
+    #[test]
+    fn recorder_stops_at_frame_limit() {
+        let mut recorder = GifRecorder::new();
+        recorder.set_limits(2, 0);
+        recorder.start_recording(4, 4, 100).unwrap();
+
+        assert_eq!(recorder.limit_reached(), None);
+        recorder.capture_grid_frame(&[0, 1, 0, 1], 2, &COLORS).unwrap();
+        assert_eq!(recorder.limit_reached(), None);
+        recorder.capture_grid_frame(&[1, 0, 1, 0], 2, &COLORS).unwrap();
+        assert_eq!(
+            recorder.limit_reached().as_deref(),
+            Some("Recording limit reached: 2 frames captured (max 2)")
+        );
+
+        // What was captured can still be saved.
+        let gif = recorder.stop_recording().unwrap();
+        assert!(gif.starts_with(b"GIF89a"));
+    }
+
+    #[test]
+    fn recorder_stops_at_byte_limit() {
+        let mut recorder = GifRecorder::new();
+        recorder.set_limits(0, 64);
+        recorder.start_recording(16, 16, 100).unwrap();
+        assert_eq!(recorder.limit_reached(), None);
+
+        let mut frame = 0;
+        while recorder.limit_reached().is_none() {
+            let state: Vec<u8> = (0..64).map(|i| ((i + frame) % 3 == 0) as u8).collect();
+            recorder.capture_grid_frame(&state, 2, &COLORS).unwrap();
+            frame += 1;
+            assert!(frame < 100, "byte limit never reached");
+        }
+        assert!(recorder.bytes_written() >= 64);
+        assert!(recorder.limit_reached().unwrap().contains("bytes written (max 64)"));
+    }
+
+    #[test]
+    fn exporter_stops_at_frame_limit() {
+        let mut exporter = AnimationExporter::new(2, 2).unwrap();
+        exporter.set_limits(2, 0);
+        for _ in 0..2 {
+            assert_eq!(exporter.limit_reached(), None);
+            exporter.add_frame(&[0; 12], 100).unwrap();
+        }
+        assert_eq!(
+            exporter.limit_reached().as_deref(),
+            Some("Export limit reached: 2 frames added (max 2)")
+        );
+        assert_eq!(exporter.frame_count(), 2);
+
+        // What was added can still be exported.
+        assert!(exporter.export_gif().unwrap().starts_with(b"GIF89a"));
+        exporter.clear();
+        assert_eq!(exporter.limit_reached(), None);
+    }
+
+    #[test]
+    fn exporter_stops_at_byte_limit() {
+        let mut exporter = AnimationExporter::new(2, 2).unwrap();
+        // Room for two 12 byte frames but not a third
+        exporter.set_limits(0, 30);
+        for _ in 0..2 {
+            assert_eq!(exporter.limit_reached(), None);
+            exporter.add_frame(&[0; 12], 100).unwrap();
+        }
+        assert_eq!(exporter.bytes_buffered(), 24);
+        assert!(exporter.limit_reached().unwrap().contains("maximum of 30"));
+    }
+
+    #[test]
+    fn sprite_sheet_layout_is_bounded() {
+        let mut exporter = AnimationExporter::new(2, 2).unwrap();
+        for _ in 0..5 {
+            exporter.add_frame(&[0; 12], 100).unwrap();
+        }
+        assert_eq!(exporter.sprite_sheet_layout(0), Ok((3, 2)));
+        assert_eq!(exporter.sprite_sheet_layout(2), Ok((2, 3)));
+        assert_eq!(exporter.sprite_sheet_layout(10), Ok((5, 1)));
+
+        // The padded sheet counts against the byte limit
+        exporter.max_bytes = 5 * 12;
+        assert_eq!(exporter.sprite_sheet_layout(5), Ok((5, 1)));
+        assert!(exporter.sprite_sheet_layout(2).is_err());
+
+        let mut large = AnimationExporter::new(u16::MAX as usize, u16::MAX as usize).unwrap();
+        large.frames = vec![Vec::new(); 2];
+        large.delays_ms = vec![100; 2];
+        assert!(large.sprite_sheet_layout(0).unwrap_err().contains("too large"));
+    }
+
+    #[test]
+    fn exporter_and_recorder_round_delays_alike() {
+        let mut recorder = GifRecorder::new();
+        for (ms, centiseconds) in [(0, 0), (1, 1), (10, 1), (15, 2), (100, 10), (101, 11), (u16::MAX, 6554)] {
+            assert_eq!(delay_centiseconds(ms), centiseconds);
+            recorder.frame_delay_ms = ms;
+            assert_eq!(recorder.delay_centiseconds(), centiseconds);
+        }
+
+        let mut exporter = AnimationExporter::new(2, 2).unwrap();
+        exporter.add_frame(&[0; 12], 15).unwrap();
+        let gif = exporter.export_gif().unwrap();
+        let mut decoder = gif::DecodeOptions::new().read_info(gif.as_slice()).unwrap();
+        assert_eq!(decoder.read_next_frame().unwrap().unwrap().delay, 2);
+    }
+
+    #[test]
+    fn recorder_without_limits_never_stops() {
+        let mut recorder = GifRecorder::new();
+        recorder.set_limits(0, 0);
+        recorder.start_recording(4, 4, 100).unwrap();
+        for i in 0..10u8 {
+            recorder.capture_grid_frame(&[i % 2, 0, 0, 0], 2, &COLORS).unwrap();
+        }
+        assert_eq!(recorder.limit_reached(), None);
+    }
+}